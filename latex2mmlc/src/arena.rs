@@ -57,6 +57,7 @@ impl Arena {
         }
     }
     #[inline(always)]
+    #[allow(clippy::mut_from_ref)]
     fn try_alloc_slice_copy<T>(&self, src: &[T]) -> Result<&mut [T], AllocErr>
     where
         T: Copy,
@@ -263,6 +264,7 @@ impl<'arena> IntoIterator for NodeList<'arena> {
     }
 }
 
+#[derive(Clone)]
 pub struct NodeListIterator<'arena, 'iterator> {
     current: Option<&'iterator NodeListElement<'arena>>,
 }
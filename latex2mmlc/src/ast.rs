@@ -2,7 +2,11 @@
 use serde::Serialize;
 
 use crate::arena::NodeList;
-use crate::attribute::{Accent, Align, FracAttr, MathSpacing, MathVariant, OpAttr, Style};
+use crate::attribute::{
+    Accent, Align, FracAttr, HtmlAttr, LineStyle, MathSpacing, MathVariant, Notation, OpAttr,
+    Padding, Style, TagPosition,
+};
+use crate::color::Color;
 use crate::ops::Op;
 
 /// AST node
@@ -19,9 +23,15 @@ pub enum Node<'arena> {
         op: Op,
         left: Option<MathSpacing>,
         right: Option<MathSpacing>,
+        attr: Option<OpAttr>,
     },
     MultiLetterIdent(&'arena str),
-    Space(&'static str),
+    /// A multi-letter operator like `lim` with limits, whose placement is given by
+    /// the `movablelimits` attribute.
+    OperatorName(&'arena str, Option<OpAttr>),
+    /// A multi-letter binary operator like the `mod` of `\bmod`.
+    BinaryOperatorName(&'arena str, MathSpacing),
+    Space(&'arena str),
     Subscript {
         target: &'arena Node<'arena>,
         symbol: &'arena Node<'arena>,
@@ -66,7 +76,6 @@ pub enum Node<'arena> {
         style: Option<Style>,
     },
     PseudoRow(NodeList<'arena>),
-    Mathstrut,
     Fenced {
         open: Op,
         close: Op,
@@ -84,13 +93,93 @@ pub enum Node<'arena> {
         content: NodeList<'arena>,
         align: Align,
         attr: Option<FracAttr>,
+        /// If set, every row gets an extra column on both sides, one of which holds the tag.
+        tags: Option<TagPosition>,
+    },
+    /// An `array` with a column specification.
+    Array {
+        content: NodeList<'arena>,
+        /// The columns, as a list of `Node::ArrayColumn`.
+        columns: NodeList<'arena>,
+        style: Option<Style>,
+    },
+    /// A column of an `array`, with the inline CSS for its cells.
+    ///
+    /// `material` is the content of `@{...}` or `!{...}`, which is repeated in every row.
+    ArrayColumn {
+        style: &'arena str,
+        material: Option<&'arena Node<'arena>>,
+    },
+    /// A horizontal rule in an `array`, which is drawn as a border of the adjacent cells.
+    /// If `columns` is given, the rule only spans these columns (counting from 1).
+    HorizontalRule {
+        line: LineStyle,
+        columns: Option<(usize, usize)>,
     },
     ColumnSeparator,
     RowSeparator,
+    /// The tag of a row, like `(1)`, which is emitted in an extra column of the table.
+    ///
+    /// The tag is made up of text, which is escaped when it is emitted, and math.
+    Tag(NodeList<'arena>),
+    /// `\notag`, which suppresses the automatic number of a row.
+    NoTag,
+    /// `\label{key}`, which becomes the `id` of the row.
+    Label(&'arena str),
     Slashed(&'arena Node<'arena>),
+    /// A base with any number of scripts before and after it.
+    ///
+    /// The scripts come in pairs of subscript and superscript, where a missing script
+    /// is an empty row.
     Multiscript {
         base: &'arena Node<'arena>,
-        sub: &'arena Node<'arena>,
+        pre: NodeList<'arena>,
+        post: NodeList<'arena>,
+    },
+    Colored {
+        color: Color,
+        content: NodeList<'arena>,
+    },
+    /// A link from `\href` or `\url`.
+    Link {
+        href: &'arena str,
+        content: NodeList<'arena>,
+    },
+    /// An attribute from a command like `\htmlClass`.
+    HtmlAttribute {
+        attr: HtmlAttr,
+        value: &'arena str,
+        content: NodeList<'arena>,
+    },
+    /// A box with a background color and/or a frame, which is drawn with CSS.
+    Framed {
+        content: &'arena Node<'arena>,
+        background: Option<Color>,
+        /// Inline CSS for the padding and the border.
+        style: &'arena str,
+    },
+    Enclose {
+        content: &'arena Node<'arena>,
+        notation: Notation,
+    },
+    /// A filled box like `\rule[raise]{width}{height}`; all dimensions are CSS lengths.
+    Rule {
+        width: &'arena str,
+        height: &'arena str,
+        depth: Option<&'arena str>,
+        raise: Option<&'arena str>,
+    },
+    /// Phantoms, smashed content and overlaps, which are all emitted as `<mpadded>`.
+    Padded {
+        content: &'arena Node<'arena>,
+        padding: Padding,
+    },
+    /// An extensible arrow like `\xrightarrow`, which stretches to fit its labels.
+    XArrow {
+        /// The arrow itself, usually a stretchy operator.
+        arrow: &'arena Node<'arena>,
+        above: &'arena Node<'arena>,
+        below: Option<&'arena Node<'arena>>,
     },
 }
 
@@ -137,7 +226,14 @@ impl<'arena> Node<'arena> {
 
         if !matches!(
             self,
-            Node::PseudoRow(_) | Node::ColumnSeparator | Node::RowSeparator
+            Node::PseudoRow(_)
+                | Node::ColumnSeparator
+                | Node::RowSeparator
+                | Node::ArrayColumn { .. }
+                | Node::HorizontalRule { .. }
+                | Node::Tag(_)
+                | Node::NoTag
+                | Node::Label(_)
         ) {
             // Get the base indent out of the way.
             new_line_and_indent(s, base_indent);
@@ -168,7 +264,12 @@ impl<'arena> Node<'arena> {
                 };
                 push!(s, "<mo>", op, "</mo>");
             }
-            Node::OperatorWithSpacing { op, left, right } => {
+            Node::OperatorWithSpacing {
+                op,
+                left,
+                right,
+                attr,
+            } => {
                 match (left, right) {
                     (Some(left), Some(right)) => {
                         push!(s, "<mo lspace=\"", left, "\" rspace=\"", right, "\"",)
@@ -181,12 +282,34 @@ impl<'arena> Node<'arena> {
                     }
                     (None, None) => s.push_str("<mo"),
                 }
+                if let Some(attr) = attr {
+                    push!(s, attr);
+                }
                 push!(s, ">", @op, "</mo>");
             }
             Node::MultiLetterIdent(letters) => {
                 push!(s, "<mi>", letters, "</mi>");
             }
-            Node::Space(space) => push!(s, "<mspace width=\"", space, "em\"/>"),
+            Node::OperatorName(name, attr) => {
+                push!(s, r#"<mo lspace="0em" rspace="0.1667em""#);
+                if let Some(attr) = attr {
+                    push!(s, attr);
+                }
+                push!(s, ">", name, "</mo>");
+            }
+            Node::BinaryOperatorName(name, spacing) => {
+                push!(
+                    s,
+                    "<mo lspace=\"",
+                    spacing,
+                    "\" rspace=\"",
+                    spacing,
+                    "\">",
+                    name,
+                    "</mo>"
+                );
+            }
+            Node::Space(space) => push!(s, "<mspace width=\"", space, "\"/>"),
             // The following nodes have exactly two children.
             node @ (Node::Subscript {
                 symbol: second,
@@ -242,12 +365,14 @@ impl<'arena> Node<'arena> {
                 third.emit(s, child_indent);
                 pushln!(s, base_indent, close);
             }
-            Node::Multiscript { base, sub } => {
+            Node::Multiscript { base, pre, post } => {
                 push!(s, "<mmultiscripts>");
                 base.emit(s, child_indent);
-                pushln!(s, child_indent, "<mprescripts/>");
-                sub.emit(s, child_indent);
-                pushln!(s, child_indent, "<mrow></mrow>");
+                emit_scripts(s, post, child_indent);
+                if !pre.is_empty() {
+                    pushln!(s, child_indent, "<mprescripts/>");
+                    emit_scripts(s, pre, child_indent);
+                }
                 pushln!(s, base_indent, "</mmultiscripts>");
             }
             Node::OverOp(op, acc, attr, target) => {
@@ -299,12 +424,6 @@ impl<'arena> Node<'arena> {
                     node.emit(s, base_indent);
                 }
             }
-            Node::Mathstrut => {
-                push!(
-                    s,
-                    r#"<mpadded width="0" style="visibility:hidden"><mo stretchy="false">(</mo></mpadded>"#
-                );
-            }
             Node::Fenced {
                 open,
                 close,
@@ -366,6 +485,7 @@ impl<'arena> Node<'arena> {
                 content,
                 align,
                 attr,
+                tags,
             } => {
                 let child_indent2 = if base_indent > 0 {
                     child_indent.saturating_add(1)
@@ -385,6 +505,14 @@ impl<'arena> Node<'arena> {
                     Align::Alternating => {
                         r#"<mtd style="text-align: -webkit-right; text-align: -moz-right; padding-right: 0">"#
                     }
+                    Align::AlternatingTight => {
+                        r#"<mtd style="text-align: -webkit-right; text-align: -moz-right; padding-left: 0; padding-right: 0">"#
+                    }
+                    Align::Multline => "<mtd>",
+                    Align::CompactCenter => r#"<mtd style="padding: 0">"#,
+                    Align::CompactLeft => {
+                        r#"<mtd style="text-align: -webkit-left; text-align: -moz-left; padding: 0">"#
+                    }
                 };
                 let even_col = match align {
                     Align::Center => "<mtd>",
@@ -392,47 +520,451 @@ impl<'arena> Node<'arena> {
                         "<mtd style=\"text-align: -webkit-left; text-align: -moz-left; padding-right: 0; padding-left: 1em\">"
                     }
                     Align::Alternating => "<mtd style=\"text-align: -webkit-left; text-align: -moz-left; padding-left: 0\">",
+                    Align::AlternatingTight => {
+                        r#"<mtd style="text-align: -webkit-left; text-align: -moz-left; padding-left: 0; padding-right: 0">"#
+                    }
+                    Align::Multline => "<mtd>",
+                    Align::CompactCenter => r#"<mtd style="padding: 0">"#,
+                    Align::CompactLeft => {
+                        r#"<mtd style="text-align: -webkit-left; text-align: -moz-left; padding: 0">"#
+                    }
                 };
 
-                let mut col: usize = 1;
+                // In `multline`, the first row is on the left and the last row on the right.
+                let row_count = match align {
+                    Align::Multline => {
+                        1 + content
+                            .iter()
+                            .filter(|node| matches!(node, Node::RowSeparator))
+                            .count()
+                    }
+                    _ => 1,
+                };
+                let first_col = |row: usize| match align {
+                    Align::Multline if row_count > 1 && row == 0 => {
+                        r#"<mtd style="text-align: -webkit-left; text-align: -moz-left">"#
+                    }
+                    Align::Multline if row_count > 1 && row + 1 == row_count => {
+                        r#"<mtd style="text-align: -webkit-right; text-align: -moz-right">"#
+                    }
+                    _ => odd_col,
+                };
+
+                let mut nodes = content.iter();
+                let mut row: usize = 0;
                 push!(s, "<mtable");
                 if let Some(attr) = attr {
                     push!(s, attr);
                 }
                 push!(s, ">");
-                pushln!(s, child_indent, "<mtr>");
-                pushln!(s, child_indent2, odd_col);
+                loop {
+                    // Look ahead for the tag and the label of the row.
+                    let mut tag = None;
+                    let mut label = None;
+                    for node in nodes.clone() {
+                        match node {
+                            Node::Tag(content) => tag = Some(content),
+                            Node::Label(key) => label = Some(*key),
+                            Node::RowSeparator => break,
+                            _ => {}
+                        }
+                    }
+                    pushln!(s, child_indent, "<mtr");
+                    if let Some(label) = label {
+                        push!(s, " id=\"", label, "\"");
+                    }
+                    push!(s, ">");
+                    match tags {
+                        Some(TagPosition::Left) => push_tag_cell(s, child_indent2, TAG_LEFT, tag),
+                        Some(TagPosition::Right) => {
+                            push_tag_cell(s, child_indent2, TAG_PADDING, None)
+                        }
+                        None => {}
+                    }
+                    pushln!(s, child_indent2, first_col(row));
+                    let mut col: usize = 1;
+                    let mut last_row = true;
+                    for node in nodes.by_ref() {
+                        match node {
+                            Node::ColumnSeparator => {
+                                pushln!(s, child_indent2, "</mtd>");
+                                col += 1;
+                                pushln!(
+                                    s,
+                                    child_indent2,
+                                    if col % 2 == 0 { even_col } else { odd_col }
+                                );
+                            }
+                            Node::RowSeparator => {
+                                last_row = false;
+                                break;
+                            }
+                            node => {
+                                node.emit(s, child_indent3);
+                            }
+                        }
+                    }
+                    pushln!(s, child_indent2, "</mtd>");
+                    match tags {
+                        Some(TagPosition::Left) => {
+                            push_tag_cell(s, child_indent2, TAG_PADDING, None)
+                        }
+                        Some(TagPosition::Right) => push_tag_cell(s, child_indent2, TAG_RIGHT, tag),
+                        None => {}
+                    }
+                    pushln!(s, child_indent, "</mtr>");
+                    if last_row {
+                        break;
+                    }
+                    row += 1;
+                }
+                pushln!(s, base_indent, "</mtable>");
+            }
+            Node::Text(text) => {
+                push!(s, "<mtext>", text, "</mtext>");
+            }
+            Node::Colored { color, content } => {
+                push!(s, "<mrow mathcolor=\"");
+                s.extend(color.hex());
+                push!(s, "\">");
+                for node in content.iter() {
+                    node.emit(s, child_indent);
+                }
+                pushln!(s, base_indent, "</mrow>");
+            }
+            Node::Link { href, content } => {
+                push!(s, "<mrow href=\"");
+                push_escaped(s, href);
+                push!(s, "\">");
+                for node in content.iter() {
+                    node.emit(s, child_indent);
+                }
+                pushln!(s, base_indent, "</mrow>");
+            }
+            Node::HtmlAttribute {
+                attr,
+                value,
+                content,
+            } => {
+                push!(s, "<mrow");
+                if matches!(attr, HtmlAttr::Data) {
+                    // The keys have been checked by the parser.
+                    for (key, value) in value.split(',').filter_map(|item| item.split_once('=')) {
+                        push!(s, " data-", key.trim(), "=\"");
+                        push_escaped(s, value.trim());
+                        push!(s, "\"");
+                    }
+                } else {
+                    push!(s, " ", attr, "=\"");
+                    push_escaped(s, value);
+                    push!(s, "\"");
+                }
+                push!(s, ">");
                 for node in content.iter() {
-                    match node {
-                        Node::ColumnSeparator => {
-                            pushln!(s, child_indent2, "</mtd>");
+                    node.emit(s, child_indent);
+                }
+                pushln!(s, base_indent, "</mrow>");
+            }
+            Node::Framed {
+                content,
+                background,
+                style,
+            } => {
+                push!(s, "<mrow");
+                if let Some(background) = background {
+                    push!(s, " mathbackground=\"");
+                    s.extend(background.hex());
+                    push!(s, "\"");
+                }
+                if !style.is_empty() {
+                    push!(s, " style=\"", style, "\"");
+                }
+                push!(s, ">");
+                content.emit(s, child_indent);
+                pushln!(s, base_indent, "</mrow>");
+            }
+            Node::Enclose { content, notation } => {
+                push!(s, "<mrow", notation, ">");
+                content.emit(s, child_indent);
+                pushln!(s, base_indent, "</mrow>");
+            }
+            Node::Rule {
+                width,
+                height,
+                depth,
+                raise,
+            } => {
+                if let Some(raise) = raise {
+                    // The raised rule is only moved visually, its box stays where it is.
+                    push!(s, "<mpadded voffset=\"", raise, "\">");
+                    new_line_and_indent(s, child_indent);
+                }
+                push!(s, "<mspace width=\"", width, "\" height=\"", height, "\"");
+                if let Some(depth) = depth {
+                    push!(s, " depth=\"", depth, "\"");
+                }
+                push!(s, " style=\"background: currentcolor\"/>");
+                if raise.is_some() {
+                    pushln!(s, base_indent, "</mpadded>");
+                }
+            }
+            Node::Padded { content, padding } => {
+                push!(s, "<mpadded", padding, ">");
+                // The overlaps are moved relative to their own width.
+                let shift = match padding {
+                    Padding::LeftOverlap => Some("-100%"),
+                    Padding::CenterOverlap => Some("-50%"),
+                    _ => None,
+                };
+                match shift {
+                    Some(shift) => {
+                        let child_indent2 = if base_indent > 0 {
+                            child_indent.saturating_add(1)
+                        } else {
+                            0
+                        };
+                        pushln!(
+                            s,
+                            child_indent,
+                            "<mrow style=\"transform: translateX(",
+                            shift,
+                            ")\">"
+                        );
+                        content.emit(s, child_indent2);
+                        pushln!(s, child_indent, "</mrow>");
+                    }
+                    None => content.emit(s, child_indent),
+                }
+                pushln!(s, base_indent, "</mpadded>");
+            }
+            Node::XArrow {
+                arrow,
+                above,
+                below,
+            } => {
+                // The labels are padded, so that the arrow sticks out a bit on both sides.
+                const LABEL_OPEN: &str = r#"<mpadded width="+0.6em" lspace="0.3em">"#;
+                let child_indent2 = if base_indent > 0 {
+                    child_indent.saturating_add(1)
+                } else {
+                    0
+                };
+                let (open, close) = match below {
+                    Some(_) => ("<munderover>", "</munderover>"),
+                    None => ("<mover>", "</mover>"),
+                };
+                push!(s, open);
+                arrow.emit(s, child_indent);
+                if let Some(below) = below {
+                    pushln!(s, child_indent, LABEL_OPEN);
+                    below.emit(s, child_indent2);
+                    pushln!(s, child_indent, "</mpadded>");
+                }
+                pushln!(s, child_indent, LABEL_OPEN);
+                above.emit(s, child_indent2);
+                pushln!(s, child_indent, "</mpadded>");
+                pushln!(s, base_indent, close);
+            }
+            Node::Array {
+                content,
+                columns,
+                style,
+            } => {
+                let child_indent2 = if base_indent > 0 {
+                    child_indent.saturating_add(1)
+                } else {
+                    0
+                };
+                let child_indent3 = if base_indent > 0 {
+                    child_indent2.saturating_add(1)
+                } else {
+                    0
+                };
+                let rule_count = |row: &[&Node]| {
+                    row.iter()
+                        .take_while(|node| matches!(node, Node::HorizontalRule { .. }))
+                        .count()
+                };
+                // Each row starts with the horizontal rules above it.
+                let nodes: Vec<&Node> = content.iter().collect();
+                let mut rows: Vec<&[&Node]> = nodes
+                    .split(|node| matches!(node, Node::RowSeparator))
+                    .collect();
+                // A `\\` at the end doesn't start a new row, but it may be followed by
+                // the rules below the last row.
+                let mut last_rules: &[&Node] = &[];
+                if let [.., _, last] = rows[..] {
+                    if rule_count(last) == last.len() {
+                        last_rules = last;
+                        rows.pop();
+                    }
+                }
+
+                push!(s, "<mtable");
+                if let Some(style) = style {
+                    push!(s, style);
+                }
+                push!(s, ">");
+                for (i, row) in rows.iter().enumerate() {
+                    let (rules, cells) = row.split_at(rule_count(row));
+                    let above: &[&Node] = if i == 0 { rules } else { &[] };
+                    let below = match rows.get(i + 1) {
+                        Some(next) => &next[..rule_count(next)],
+                        None => last_rules,
+                    };
+                    let mut cells = cells.split(|node| matches!(node, Node::ColumnSeparator));
+                    let mut col: usize = 0;
+                    pushln!(s, child_indent, "<mtr>");
+                    for column in columns.iter() {
+                        let Node::ArrayColumn { style, material } = column else {
+                            continue;
+                        };
+                        if material.is_none() {
                             col += 1;
-                            pushln!(
-                                s,
-                                child_indent2,
-                                if col % 2 == 0 { even_col } else { odd_col }
-                            );
                         }
-                        Node::RowSeparator => {
-                            pushln!(s, child_indent2, "</mtd>");
-                            pushln!(s, child_indent, "</mtr>");
-                            pushln!(s, child_indent, "<mtr>");
-                            pushln!(s, child_indent2, odd_col);
-                            col = 1;
+                        let between = material.is_some();
+                        push_cell_open(
+                            s,
+                            child_indent2,
+                            style,
+                            horizontal_rule(above, col, between),
+                            horizontal_rule(below, col, between),
+                        );
+                        match material {
+                            Some(material) => material.emit(s, child_indent3),
+                            None => {
+                                for node in cells.next().unwrap_or(&[]) {
+                                    node.emit(s, child_indent3);
+                                }
+                            }
                         }
-                        node => {
+                        pushln!(s, child_indent2, "</mtd>");
+                    }
+                    // Cells which don't have a column in the specification.
+                    for cell in cells {
+                        pushln!(s, child_indent2, "<mtd>");
+                        for node in cell {
                             node.emit(s, child_indent3);
                         }
+                        pushln!(s, child_indent2, "</mtd>");
                     }
+                    pushln!(s, child_indent, "</mtr>");
                 }
-                pushln!(s, child_indent2, "</mtd>");
-                pushln!(s, child_indent, "</mtr>");
                 pushln!(s, base_indent, "</mtable>");
             }
-            Node::Text(text) => {
-                push!(s, "<mtext>", text, "</mtext>");
+            Node::ColumnSeparator
+            | Node::RowSeparator
+            | Node::ArrayColumn { .. }
+            | Node::HorizontalRule { .. }
+            | Node::Tag(_)
+            | Node::NoTag
+            | Node::Label(_) => (),
+        }
+    }
+}
+
+/// The extra cells on both sides of a table with tags, which take up the remaining width.
+const TAG_PADDING: &str = r#"<mtd style="width: 50%">"#;
+const TAG_LEFT: &str =
+    r#"<mtd style="width: 50%; text-align: -webkit-left; text-align: -moz-left">"#;
+const TAG_RIGHT: &str =
+    r#"<mtd style="width: 50%; text-align: -webkit-right; text-align: -moz-right">"#;
+
+fn push_tag_cell<'arena>(
+    s: &mut String,
+    indent: usize,
+    open: &str,
+    tag: Option<&'arena NodeList<'arena>>,
+) {
+    pushln!(s, indent, open);
+    if let Some(tag) = tag {
+        let child_indent = if indent > 0 { indent + 1 } else { 0 };
+        for node in tag.iter() {
+            match node {
+                // The text of a tag may contain characters like `&` and `<`.
+                Node::Text(text) => {
+                    pushln!(s, child_indent, "<mtext>");
+                    push_escaped(s, text);
+                    push!(s, "</mtext>");
+                }
+                node => node.emit(s, child_indent),
             }
-            Node::ColumnSeparator | Node::RowSeparator => (),
+        }
+    }
+    pushln!(s, indent, "</mtd>");
+}
+
+/// Open a cell of an `array`, with the rules above and below it as borders.
+fn push_cell_open(
+    s: &mut String,
+    indent: usize,
+    style: &str,
+    above: Option<LineStyle>,
+    below: Option<LineStyle>,
+) {
+    pushln!(s, indent, "<mtd");
+    if style.is_empty() && above.is_none() && below.is_none() {
+        push!(s, ">");
+        return;
+    }
+    push!(s, " style=\"", style);
+    let mut separator = if style.is_empty() { "" } else { "; " };
+    if let Some(line) = above {
+        push!(s, separator, "border-top: ", line);
+        separator = "; ";
+    }
+    if let Some(line) = below {
+        push!(s, separator, "border-bottom: ", line);
+    }
+    push!(s, "\">");
+}
+
+/// Find the horizontal rule which applies to the given column.
+///
+/// `col` counts the columns from 1; `between` means that the cell is the material
+/// after column `col`. Multiple rules result in a double rule.
+fn horizontal_rule(rules: &[&Node], col: usize, between: bool) -> Option<LineStyle> {
+    let mut result = None;
+    for rule in rules {
+        let Node::HorizontalRule { line, columns } = rule else {
+            continue;
+        };
+        let applies = match columns {
+            None => true,
+            Some((first, last)) => *first <= col && (col < *last || (!between && col == *last)),
+        };
+        if applies {
+            result = Some(match result {
+                None => *line,
+                Some(_) => LineStyle::Double,
+            });
+        }
+    }
+    result
+}
+
+/// Emit the scripts of `<mmultiscripts>`, where missing scripts are `<none/>`.
+fn emit_scripts<'arena>(s: &mut String, scripts: &'arena NodeList<'arena>, indent: usize) {
+    for node in scripts.iter() {
+        match node {
+            Node::Row { nodes, .. } if nodes.is_empty() => {
+                pushln!(s, indent, "<none/>");
+            }
+            node => node.emit(s, indent),
+        }
+    }
+}
+
+/// Push text or an attribute value, with the characters escaped which have a meaning in markup.
+fn push_escaped(s: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => push!(s, "&amp;"),
+            '"' => push!(s, "&quot;"),
+            '\'' => push!(s, "&#39;"),
+            '<' => push!(s, "&lt;"),
+            '>' => push!(s, "&gt;"),
+            c => push!(s, @c),
         }
     }
 }
@@ -453,7 +985,7 @@ mod tests {
 
     #[test]
     fn node_display() {
-        let problems = vec![
+        let problems = [
             (Node::Number("3.14"), "<mn>3.14</mn>"),
             (Node::SingleLetterIdent('x', None), "<mi>x</mi>"),
             (Node::SingleLetterIdent('α', None), "<mi>α</mi>"),
@@ -1,7 +1,7 @@
 #[cfg(test)]
 use serde::Serialize;
 
-use strum_macros::AsRefStr;
+use strum_macros::{AsRefStr, IntoStaticStr};
 
 /// <mi> mathvariant attribute
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -18,7 +18,22 @@ impl AsRef<str> for MathVariant {
     }
 }
 
-#[derive(Debug, PartialEq, AsRefStr)]
+/// An attribute which is set with `\htmlClass`, `\htmlId`, `\htmlStyle` or `\htmlData`.
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
+#[cfg_attr(test, derive(Serialize))]
+pub enum HtmlAttr {
+    #[strum(serialize = "class")]
+    Class,
+    #[strum(serialize = "id")]
+    Id,
+    #[strum(serialize = "style")]
+    Style,
+    /// `data-*` attributes, which are given as a list like `key=value, other=value`.
+    #[strum(serialize = "data")]
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
 #[cfg_attr(test, derive(Serialize))]
 pub enum Accent {
     #[strum(serialize = "true")]
@@ -36,6 +51,20 @@ pub enum OpAttr {
     StretchyFalse,
     #[strum(serialize = r#" movablelimits="false""#)]
     NoMovableLimits,
+    #[strum(serialize = r#" movablelimits="true""#)]
+    MovableLimits,
+}
+
+/// Where the bounds of an operator are placed, as set by `\limits`, `\nolimits`
+/// and `\displaylimits`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limits {
+    /// Below and above the operator.
+    Always = 1,
+    /// As sub- and superscripts.
+    Never,
+    /// Below and above in display style, and as scripts otherwise.
+    Display,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -67,6 +96,19 @@ pub enum FracAttr {
     DisplayStyleFalse,
     #[strum(serialize = r#" displaystyle="true" scriptlevel="0" style="padding-top: 0.1667em""#)]
     CFracStyle,
+    #[strum(serialize = r#" displaystyle="false" scriptlevel="1""#)]
+    ScriptStyle,
+    #[strum(serialize = r#" displaystyle="true" style="width: 100%""#)]
+    DisplayStyleFullWidth,
+}
+
+/// The side of the equation tags in a numbered environment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Serialize))]
+pub enum TagPosition {
+    Right,
+    /// Like the `leqno` option of LaTeX.
+    Left,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
@@ -82,21 +124,147 @@ pub enum Style {
     ScriptScriptStyle,
 }
 
+/// Invisible content, or content with some of its dimensions set to zero.
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
+#[cfg_attr(test, derive(Serialize))]
+pub enum Padding {
+    /// `\phantom`: invisible, but takes up the same space.
+    #[strum(serialize = r#" style="visibility:hidden""#)]
+    Phantom = 1,
+    /// `\hphantom`: invisible, and only keeps the width.
+    #[strum(serialize = r#" height="0" depth="0" style="visibility:hidden""#)]
+    HPhantom,
+    /// `\vphantom`: invisible, and only keeps the height and the depth.
+    #[strum(serialize = r#" width="0" style="visibility:hidden""#)]
+    VPhantom,
+    /// `\smash`: zero height and depth.
+    #[strum(serialize = r#" height="0" depth="0""#)]
+    Smash,
+    /// `\smash[t]`: zero height.
+    #[strum(serialize = r#" height="0""#)]
+    SmashTop,
+    /// `\smash[b]`: zero depth.
+    #[strum(serialize = r#" depth="0""#)]
+    SmashBottom,
+    /// `\mathllap`: zero width, with the content sticking out to the left.
+    ///
+    /// MathML Core doesn't support percentages for `lspace`, so the content is
+    /// moved with a CSS transform instead.
+    #[strum(serialize = r#" width="0""#)]
+    LeftOverlap,
+    /// `\mathrlap`: zero width, with the content sticking out to the right.
+    #[strum(serialize = r#" width="0""#)]
+    RightOverlap,
+    /// `\mathclap`: zero width, with the content centered on that point.
+    #[strum(serialize = r#" width="0""#)]
+    CenterOverlap,
+}
+
+/// Strokes through the content, like in the `cancel` package.
+///
+/// MathML Core doesn't have `<menclose>`, so the strokes are drawn as CSS gradients.
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
+#[cfg_attr(test, derive(Serialize))]
+pub enum Notation {
+    /// `\cancel`: a stroke from the bottom left to the top right.
+    #[strum(
+        serialize = r#" style="background: linear-gradient(to top left, transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0)""#
+    )]
+    UpDiagonalStrike = 1,
+    /// `\bcancel`: a stroke from the top left to the bottom right.
+    #[strum(
+        serialize = r#" style="background: linear-gradient(to top right, transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0)""#
+    )]
+    DownDiagonalStrike,
+    /// `\xcancel`: both diagonal strokes.
+    #[strum(
+        serialize = r#" style="background: linear-gradient(to top left, transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0), linear-gradient(to top right, transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0)""#
+    )]
+    CrossStrike,
+    /// `\sout`: a horizontal stroke.
+    #[strum(
+        serialize = r#" style="background: linear-gradient(transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0)""#
+    )]
+    HorizontalStrike,
+    /// `\cancelto`: a stroke from the bottom left to the top right with an arrowhead,
+    /// which is made up of two short lines in the top right corner.
+    #[strum(
+        serialize = r#" style="background: linear-gradient(currentcolor, currentcolor) top right / 0.4em 0.06em no-repeat, linear-gradient(currentcolor, currentcolor) top right / 0.06em 0.4em no-repeat, linear-gradient(to top left, transparent calc(50% - 0.03em), currentcolor 0 calc(50% + 0.03em), transparent 0)""#
+    )]
+    UpDiagonalArrow,
+}
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(Serialize))]
 pub enum Align {
     Center,
     Left,
     Alternating,
+    /// Like `Alternating`, but without space between the pairs of columns, for `alignat`.
+    AlternatingTight,
+    /// The first row on the left, the last row on the right and the others centered.
+    Multline,
+    /// Centered columns without any padding, for `\substack` and `subarray`.
+    CompactCenter,
+    /// Left-aligned columns without any padding, for `subarray`.
+    CompactLeft,
 }
 
+/// The CSS border of a rule in an `array`.
 #[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
 #[cfg_attr(test, derive(Serialize))]
+pub enum LineStyle {
+    #[strum(serialize = "0.06em solid")]
+    Solid = 1,
+    #[strum(serialize = "0.06em dashed")]
+    Dashed,
+    /// Two rules next to each other, like `||` or `\hline\hline`.
+    #[strum(serialize = "0.18em double")]
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr, IntoStaticStr)]
+#[cfg_attr(test, derive(Serialize))]
 pub enum MathSpacing {
     #[strum(serialize = "0em")]
     Zero = 1,
+    #[strum(serialize = "0.1667em")]
+    ThreeMu, // 3/18 of an em/\quad
     #[strum(serialize = "0.2222em")]
     FourMu, // 4/18 of an em/\quad
+    #[strum(serialize = "0.2778em")]
+    FiveMu, // 5/18 of an em/\quad
+}
+
+/// The class of an atom in TeX, which can be set with commands like `\mathrel`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathClass {
+    Ord = 1,
+    Op,
+    Bin,
+    Rel,
+    Open,
+    Close,
+    Punct,
+    Inner,
+}
+
+impl MathClass {
+    /// The space on the left and on the right of an atom of this class.
+    ///
+    /// In TeX, the space between two atoms depends on both of their classes;
+    /// these are the spaces next to an ordinary atom.
+    pub(crate) fn spacing(self) -> (MathSpacing, MathSpacing) {
+        match self {
+            MathClass::Ord | MathClass::Open | MathClass::Close => {
+                (MathSpacing::Zero, MathSpacing::Zero)
+            }
+            MathClass::Op | MathClass::Inner => (MathSpacing::ThreeMu, MathSpacing::ThreeMu),
+            MathClass::Bin => (MathSpacing::FourMu, MathSpacing::FourMu),
+            MathClass::Rel => (MathSpacing::FiveMu, MathSpacing::FiveMu),
+            MathClass::Punct => (MathSpacing::Zero, MathSpacing::ThreeMu),
+        }
+    }
 }
 
 // Transform of unicode characters.
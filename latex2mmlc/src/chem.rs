@@ -0,0 +1,514 @@
+//! Chemistry
+//!
+//! The `\ce` and `\pu` commands of the mhchem package have their own syntax, in which
+//! `H2O` is a formula with a subscript and `->` is a reaction arrow. Their argument is
+//! scanned into a flat list of items here, which the parser turns into nodes. Anything
+//! which is regular LaTeX, like `$...$` or `\alpha`, is handed back to the parser as math.
+
+use crate::ops::{self, Op};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ChemItem<'a> {
+    /// LaTeX code, like a stoichiometric number or a command like `\alpha`.
+    Math(&'a str),
+    /// Upright letters, like an element symbol, a state like `aq` or a unit.
+    Letters(&'a str),
+    /// A subscript of the preceding item.
+    Subscript(&'a str),
+    /// A superscript of the preceding item, like a charge or an oxidation state.
+    Superscript(&'a str),
+    /// The mass number and the atomic number of an isotope, like in `^{14}_{6}C`.
+    Isotope(Option<&'a str>, Option<&'a str>),
+    /// An operator with its usual spacing, like the `+` between two formulas.
+    Operator(Op),
+    /// A bond, which is drawn without any space around it.
+    Bond(Op),
+    /// A parenthesis or bracket, which does not stretch.
+    Fence(Op),
+    /// A reaction arrow, with optional labels above and below it.
+    Arrow {
+        arrow: ChemArrow,
+        above: Option<&'a str>,
+        below: Option<&'a str>,
+    },
+    /// A thin space, like between a number and its unit.
+    Space,
+}
+
+/// The kind of a reaction arrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ChemArrow {
+    /// An arrow which is a single character.
+    Single(Op),
+    /// An equilibrium which lies on one side, like `<=>>`, with a long and a short harpoon.
+    Unbalanced { favors_products: bool },
+}
+
+/// The reaction arrows, longest first, so that `<-->` is not read as `<-`.
+const ARROWS: [(&str, ChemArrow); 7] = [
+    (
+        "<=>>",
+        ChemArrow::Unbalanced {
+            favors_products: true,
+        },
+    ),
+    (
+        "<<=>",
+        ChemArrow::Unbalanced {
+            favors_products: false,
+        },
+    ),
+    (
+        "<-->",
+        ChemArrow::Single(ops::RIGHTWARDS_ARROW_OVER_LEFTWARDS_ARROW),
+    ),
+    (
+        "<=>",
+        ChemArrow::Single(ops::RIGHTWARDS_HARPOON_OVER_LEFTWARDS_HARPOON),
+    ),
+    ("<->", ChemArrow::Single(ops::LEFT_RIGHT_ARROW)),
+    ("->", ChemArrow::Single(ops::RIGHTWARDS_ARROW)),
+    ("<-", ChemArrow::Single(ops::LEFTWARDS_ARROW)),
+];
+
+struct Scanner<'a> {
+    rest: &'a str,
+    items: Vec<ChemItem<'a>>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(input: &'a str) -> Self {
+        Scanner {
+            rest: input,
+            items: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    /// The character after the next one.
+    #[inline]
+    fn peek_second(&self) -> Option<char> {
+        self.rest.chars().nth(1)
+    }
+
+    /// Whether the next character ends a word, i.e. it is whitespace or the end.
+    fn word_ends_after_next(&self) -> bool {
+        self.peek_second().map_or(true, |c| c.is_whitespace())
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let trimmed = self.rest.trim_start();
+        let skipped = trimmed.len() != self.rest.len();
+        self.rest = trimmed;
+        skipped
+    }
+
+    fn take(&mut self, len: usize) -> &'a str {
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let len = self.rest.find(|c| !f(c)).unwrap_or(self.rest.len());
+        self.take(len)
+    }
+
+    /// Read everything up to the `close` character, after the opening character at the
+    /// start of the input, and return it without the delimiters.
+    ///
+    /// Braces are taken into account, so that `[{]}]` is read as `{]}`.
+    fn read_balanced(&mut self, close: char) -> Option<&'a str> {
+        let open_len = self.peek()?.len_utf8();
+        let mut depth = 0usize;
+        let mut escaped = false;
+        for (i, c) in self.rest[open_len..].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                c if c == close && depth == 0 => {
+                    let content = self.take(open_len + i + c.len_utf8());
+                    return Some(&content[open_len..open_len + i]);
+                }
+                '{' => depth += 1,
+                '}' => depth = depth.checked_sub(1)?,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Read a command like `\alpha` or `\frac{1}{2}`, together with its braced arguments.
+    fn read_command(&mut self) -> Option<&'a str> {
+        let start = self.rest;
+        let mut len = 1;
+        let name = &self.rest[1..];
+        len += match name.find(|c: char| !c.is_ascii_alphabetic()) {
+            Some(0) => name.chars().next()?.len_utf8(),
+            Some(name_len) => name_len,
+            None => name.len(),
+        };
+        self.take(len);
+        while self.peek() == Some('{') {
+            let group = self.read_balanced('}')?;
+            len += group.len() + 2;
+        }
+        Some(&start[..len])
+    }
+
+    /// Read the argument of `^` or `_`, after the `^` or `_` has been consumed.
+    ///
+    /// Without braces, a superscript can be a charge like `2-`, and a subscript can be a
+    /// number like `12`. Otherwise, it is a single character.
+    fn read_script(&mut self, is_sup: bool) -> Option<&'a str> {
+        match self.peek()? {
+            '{' => self.read_balanced('}'),
+            '$' => self.read_balanced('$'),
+            c if c.is_ascii_digit() || (is_sup && matches!(c, '+' | '-')) => {
+                let start = self.rest;
+                let digits = self.take_while(|c| c.is_ascii_digit()).len();
+                let signs = if is_sup {
+                    self.take_while(|c| matches!(c, '+' | '-')).len()
+                } else {
+                    0
+                };
+                Some(&start[..digits + signs])
+            }
+            c => Some(self.take(c.len_utf8())),
+        }
+    }
+
+    /// Read the arrow labels in brackets, like `[above][below]`.
+    fn read_arrow_labels(&mut self) -> Option<(Option<&'a str>, Option<&'a str>)> {
+        let mut labels = [None, None];
+        for label in labels.iter_mut() {
+            if self.peek() != Some('[') {
+                break;
+            }
+            *label = Some(self.read_balanced(']')?).filter(|l| !l.trim().is_empty());
+        }
+        let [above, below] = labels;
+        Some((above, below))
+    }
+
+    fn push(&mut self, item: ChemItem<'a>) {
+        self.items.push(item);
+    }
+}
+
+/// Scan the argument of `\ce`.
+///
+/// Returns `None` if a group, a bracket or a math part is not closed.
+pub(crate) fn scan_formula(input: &str) -> Option<Vec<ChemItem<'_>>> {
+    let mut s = Scanner::new(input);
+    // At the start of a word, numbers are stoichiometric numbers and scripts belong to
+    // the following element.
+    let mut at_start = true;
+    loop {
+        if s.skip_whitespace() {
+            at_start = true;
+        }
+        let Some(c) = s.peek() else {
+            break;
+        };
+        if let Some((name, arrow)) = ARROWS.iter().find(|(name, _)| s.rest.starts_with(name)) {
+            s.take(name.len());
+            let (above, below) = s.read_arrow_labels()?;
+            s.push(ChemItem::Arrow {
+                arrow: *arrow,
+                above,
+                below,
+            });
+            at_start = true;
+            continue;
+        }
+        let standalone = at_start && s.word_ends_after_next();
+        match c {
+            '+' | '=' if standalone => {
+                s.take(1);
+                s.push(ChemItem::Operator(Op::from_char(c)));
+            }
+            '^' if standalone => {
+                s.take(1);
+                s.push(ChemItem::Operator(ops::UPWARDS_ARROW));
+            }
+            'v' if standalone => {
+                s.take(1);
+                s.push(ChemItem::Operator(ops::DOWNWARDS_ARROW));
+            }
+            '^' | '_' if at_start => {
+                // The mass number and the atomic number of an isotope.
+                let mut mass = None;
+                let mut atomic = None;
+                while let Some(c @ ('^' | '_')) = s.peek() {
+                    s.take(1);
+                    let script = Some(s.read_script(false)?);
+                    if c == '^' {
+                        mass = script;
+                    } else {
+                        atomic = script;
+                    }
+                }
+                s.push(ChemItem::Isotope(mass, atomic));
+                // The element follows.
+                continue;
+            }
+            '^' => {
+                s.take(1);
+                let script = s.read_script(true)?;
+                s.push(ChemItem::Superscript(script));
+            }
+            '_' => {
+                s.take(1);
+                let script = s.read_script(false)?;
+                s.push(ChemItem::Subscript(script));
+            }
+            '{' if s.rest.starts_with("{}") => {
+                // An empty group, which carries the scripts of an isotope.
+                s.take(2);
+                continue;
+            }
+            '{' => {
+                let group = s.read_balanced('}')?;
+                s.push(ChemItem::Math(group));
+            }
+            '$' => {
+                let math = s.read_balanced('$')?;
+                s.push(ChemItem::Math(math));
+            }
+            '\\' => {
+                let command = s.read_command()?;
+                s.push(ChemItem::Math(command));
+            }
+            '0'..='9' if at_start => {
+                let number = s.take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '/'));
+                s.push(ChemItem::Math(number));
+                // The formula follows.
+                at_start = false;
+                continue;
+            }
+            '0'..='9' => {
+                let number = s.take_while(|c| c.is_ascii_digit());
+                s.push(ChemItem::Subscript(number));
+            }
+            '+' | '-' if s.word_ends_after_next() || matches!(s.peek_second(), Some(')' | ']')) => {
+                // A charge at the end of a formula, like in `Na+` or `OH-`.
+                let charge = s.take_while(|c| matches!(c, '+' | '-'));
+                s.push(ChemItem::Superscript(charge));
+            }
+            '-' => {
+                s.take(1);
+                s.push(ChemItem::Bond(ops::MINUS_SIGN));
+            }
+            '=' => {
+                s.take(1);
+                s.push(ChemItem::Bond(ops::EQUALS_SIGN));
+            }
+            '#' => {
+                s.take(1);
+                s.push(ChemItem::Bond(ops::IDENTICAL_TO));
+            }
+            '*' | '.' => {
+                // An addition compound, like `CuSO4*5H2O`.
+                s.take(1);
+                s.push(ChemItem::Operator(ops::MIDDLE_DOT));
+                at_start = true;
+                continue;
+            }
+            '(' | '[' | ')' | ']' => {
+                s.take(1);
+                s.push(ChemItem::Fence(Op::from_char(c)));
+            }
+            c if c.is_uppercase() => {
+                // An element symbol is an uppercase letter followed by lowercase ones.
+                let len = c.len_utf8()
+                    + s.rest[c.len_utf8()..]
+                        .find(|c: char| !c.is_lowercase())
+                        .unwrap_or(s.rest.len() - c.len_utf8());
+                let letters = s.take(len);
+                s.push(ChemItem::Letters(letters));
+            }
+            c if c.is_alphabetic() => {
+                let letters = s.take_while(|c| c.is_lowercase());
+                s.push(ChemItem::Letters(letters));
+            }
+            c => {
+                s.take(c.len_utf8());
+                s.push(ChemItem::Operator(Op::from_char(c)));
+            }
+        }
+        at_start = false;
+    }
+    Some(s.items)
+}
+
+/// Scan the argument of `\pu`, which is a number followed by units, like `1.2e3 kJ/mol`.
+///
+/// Returns `None` if a group or a math part is not closed.
+pub(crate) fn scan_units(input: &str) -> Option<Vec<ChemItem<'_>>> {
+    let mut s = Scanner::new(input.trim());
+    let mantissa = s.take_while(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+'));
+    if !mantissa.is_empty() {
+        s.push(ChemItem::Math(mantissa));
+        if matches!(s.peek(), Some('e' | 'E'))
+            && s.rest[1..].starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+'))
+        {
+            s.take(1);
+            let exponent = s.take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '+'));
+            s.push(ChemItem::Operator(ops::MIDDLE_DOT));
+            s.push(ChemItem::Math("10"));
+            s.push(ChemItem::Superscript(exponent));
+        }
+    }
+    let mut after_unit = false;
+    loop {
+        if s.skip_whitespace() {
+            after_unit = false;
+            if s.peek().is_some() && !s.items.is_empty() {
+                s.push(ChemItem::Space);
+            }
+        }
+        let Some(c) = s.peek() else {
+            break;
+        };
+        match c {
+            '*' | '.' => {
+                s.take(1);
+                s.push(ChemItem::Operator(ops::MIDDLE_DOT));
+            }
+            '/' => {
+                s.take(1);
+                s.push(ChemItem::Operator(ops::SOLIDUS));
+            }
+            '^' => {
+                s.take(1);
+                let script = s.read_script(true)?;
+                s.push(ChemItem::Superscript(script));
+            }
+            '-' | '0'..='9' if after_unit => {
+                // An exponent without `^`, like in `mol-1`.
+                let exponent = s.take_while(|c| c.is_ascii_digit() || c == '-');
+                s.push(ChemItem::Superscript(exponent));
+            }
+            '\\' => {
+                let command = s.read_command()?;
+                s.push(ChemItem::Math(command));
+            }
+            '$' => {
+                let math = s.read_balanced('$')?;
+                s.push(ChemItem::Math(math));
+            }
+            '(' | ')' => {
+                s.take(1);
+                s.push(ChemItem::Fence(Op::from_char(c)));
+            }
+            '0'..='9' | '-' => {
+                let number = s.take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-'));
+                s.push(ChemItem::Math(number));
+            }
+            _ => {
+                let unit = s.take_while(|c| {
+                    !c.is_whitespace() && !c.is_ascii_digit() && !"*./^\\$()-".contains(c)
+                });
+                s.push(ChemItem::Letters(unit));
+                after_unit = true;
+                continue;
+            }
+        }
+        after_unit = false;
+    }
+    Some(s.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formula_test() {
+        use ChemItem::*;
+        assert_eq!(
+            scan_formula("2H2O").unwrap(),
+            [Math("2"), Letters("H"), Subscript("2"), Letters("O")]
+        );
+        assert_eq!(
+            scan_formula("SO4^2- + Na+").unwrap(),
+            [
+                Letters("S"),
+                Letters("O"),
+                Subscript("4"),
+                Superscript("2-"),
+                Operator(ops::PLUS_SIGN),
+                Letters("Na"),
+                Superscript("+"),
+            ]
+        );
+        assert_eq!(
+            scan_formula("^{14}_{6}C").unwrap(),
+            [Isotope(Some("14"), Some("6")), Letters("C")]
+        );
+        assert_eq!(
+            scan_formula("A ->[H2O][\\Delta] B").unwrap(),
+            [
+                Letters("A"),
+                Arrow {
+                    arrow: ChemArrow::Single(ops::RIGHTWARDS_ARROW),
+                    above: Some("H2O"),
+                    below: Some("\\Delta"),
+                },
+                Letters("B"),
+            ]
+        );
+        assert_eq!(
+            scan_formula("CH3-CH=CH2").unwrap(),
+            [
+                Letters("C"),
+                Letters("H"),
+                Subscript("3"),
+                Bond(ops::MINUS_SIGN),
+                Letters("C"),
+                Letters("H"),
+                Bond(ops::EQUALS_SIGN),
+                Letters("C"),
+                Letters("H"),
+                Subscript("2"),
+            ]
+        );
+        assert!(scan_formula("A ->[x B").is_none());
+        assert!(scan_formula("$x").is_none());
+    }
+
+    #[test]
+    fn units_test() {
+        use ChemItem::*;
+        assert_eq!(
+            scan_units("1.2e3 kJ mol-1").unwrap(),
+            [
+                Math("1.2"),
+                Operator(ops::MIDDLE_DOT),
+                Math("10"),
+                Superscript("3"),
+                Space,
+                Letters("kJ"),
+                Space,
+                Letters("mol"),
+                Superscript("-1"),
+            ]
+        );
+        assert_eq!(
+            scan_units("3 m/s^2").unwrap(),
+            [
+                Math("3"),
+                Space,
+                Letters("m"),
+                Operator(ops::SOLIDUS),
+                Letters("s"),
+                Superscript("2"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Colors
+//!
+//! The named colors and the color expressions follow the `xcolor` package.
+//! Colors are always emitted as hex codes, because the CSS color names don't
+//! agree with the `xcolor` names (e.g., `green` is `#008000` in CSS but
+//! `#00ff00` in `xcolor`).
+
+#[cfg(test)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Serialize))]
+pub struct Color(u8, u8, u8);
+
+impl Color {
+    /// Return the color as a hex code like `#ff8000`.
+    pub(crate) fn hex(&self) -> [char; 7] {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let digit = |value: u8| DIGITS[(value & 0xf) as usize] as char;
+        [
+            '#',
+            digit(self.0 >> 4),
+            digit(self.0),
+            digit(self.1 >> 4),
+            digit(self.1),
+            digit(self.2 >> 4),
+            digit(self.2),
+        ]
+    }
+
+    /// Mix `percent` percent of `self` with the rest of `other`.
+    fn mix(self, other: Color, percent: f32) -> Color {
+        let ratio = percent.clamp(0.0, 100.0) / 100.0;
+        let mix = |a: u8, b: u8| (a as f32 * ratio + b as f32 * (1.0 - ratio)).round() as u8;
+        Color(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+const WHITE: Color = Color(0xff, 0xff, 0xff);
+
+/// The colors which are always available in `xcolor`.
+static NAMED_COLORS: phf::Map<&'static str, Color> = phf::phf_map! {
+    "black" => Color(0x00, 0x00, 0x00),
+    "blue" => Color(0x00, 0x00, 0xff),
+    "brown" => Color(0xbf, 0x80, 0x40),
+    "cyan" => Color(0x00, 0xff, 0xff),
+    "darkgray" => Color(0x40, 0x40, 0x40),
+    "gray" => Color(0x80, 0x80, 0x80),
+    "green" => Color(0x00, 0xff, 0x00),
+    "lightgray" => Color(0xbf, 0xbf, 0xbf),
+    "lime" => Color(0xbf, 0xff, 0x00),
+    "magenta" => Color(0xff, 0x00, 0xff),
+    "olive" => Color(0x80, 0x80, 0x00),
+    "orange" => Color(0xff, 0x80, 0x00),
+    "pink" => Color(0xff, 0xbf, 0xbf),
+    "purple" => Color(0xbf, 0x00, 0x40),
+    "red" => Color(0xff, 0x00, 0x00),
+    "teal" => Color(0x00, 0x80, 0x80),
+    "violet" => Color(0x80, 0x00, 0x80),
+    "white" => WHITE,
+    "yellow" => Color(0xff, 0xff, 0x00),
+};
+
+/// The colors of the `dvipsnames` option of `xcolor`, which are also known to MathJax.
+///
+/// They are defined in CMYK and converted with the same formula as `xcolor` uses.
+static DVIPS_COLORS: phf::Map<&'static str, Color> = phf::phf_map! {
+    "Apricot" => Color(0xff, 0xad, 0x7a),
+    "Aquamarine" => Color(0x2e, 0xff, 0xb3),
+    "Bittersweet" => Color(0xc2, 0x03, 0x00),
+    "Black" => Color(0x00, 0x00, 0x00),
+    "Blue" => Color(0x00, 0x00, 0xff),
+    "BlueGreen" => Color(0x26, 0xff, 0xab),
+    "BlueViolet" => Color(0x1a, 0x0d, 0xf5),
+    "BrickRed" => Color(0xb8, 0x00, 0x00),
+    "Brown" => Color(0x66, 0x00, 0x00),
+    "BurntOrange" => Color(0xff, 0x7d, 0x00),
+    "CadetBlue" => Color(0x61, 0x6e, 0xc4),
+    "CarnationPink" => Color(0xff, 0x5e, 0xff),
+    "Cerulean" => Color(0x0f, 0xe3, 0xff),
+    "CornflowerBlue" => Color(0x59, 0xde, 0xff),
+    "Cyan" => Color(0x00, 0xff, 0xff),
+    "Dandelion" => Color(0xff, 0xb5, 0x29),
+    "DarkOrchid" => Color(0x99, 0x33, 0xcc),
+    "Emerald" => Color(0x00, 0xff, 0x80),
+    "ForestGreen" => Color(0x00, 0xe0, 0x00),
+    "Fuchsia" => Color(0x73, 0x03, 0xeb),
+    "Goldenrod" => Color(0xff, 0xe6, 0x29),
+    "Gray" => Color(0x80, 0x80, 0x80),
+    "Green" => Color(0x00, 0xff, 0x00),
+    "GreenYellow" => Color(0xd9, 0xff, 0x4f),
+    "JungleGreen" => Color(0x03, 0xff, 0x7a),
+    "Lavender" => Color(0xff, 0x85, 0xff),
+    "LimeGreen" => Color(0x80, 0xff, 0x00),
+    "Magenta" => Color(0xff, 0x00, 0xff),
+    "Mahogany" => Color(0xa6, 0x00, 0x00),
+    "Maroon" => Color(0xad, 0x00, 0x00),
+    "Melon" => Color(0xff, 0x8a, 0x80),
+    "MidnightBlue" => Color(0x00, 0x70, 0x91),
+    "Mulberry" => Color(0xa3, 0x14, 0xfa),
+    "NavyBlue" => Color(0x0f, 0x75, 0xff),
+    "OliveGreen" => Color(0x00, 0x99, 0x00),
+    "Orange" => Color(0xff, 0x63, 0x21),
+    "OrangeRed" => Color(0xff, 0x00, 0x80),
+    "Orchid" => Color(0xad, 0x5c, 0xff),
+    "Peach" => Color(0xff, 0x80, 0x4d),
+    "Periwinkle" => Color(0x6e, 0x73, 0xff),
+    "PineGreen" => Color(0x00, 0xbf, 0x29),
+    "Plum" => Color(0x80, 0x00, 0xff),
+    "ProcessBlue" => Color(0x0a, 0xff, 0xff),
+    "Purple" => Color(0x8c, 0x24, 0xff),
+    "RawSienna" => Color(0x8c, 0x00, 0x00),
+    "Red" => Color(0xff, 0x00, 0x00),
+    "RedOrange" => Color(0xff, 0x3b, 0x21),
+    "RedViolet" => Color(0x96, 0x00, 0xa8),
+    "Rhodamine" => Color(0xff, 0x2e, 0xff),
+    "RoyalBlue" => Color(0x00, 0x80, 0xff),
+    "RoyalPurple" => Color(0x40, 0x1a, 0xff),
+    "RubineRed" => Color(0xff, 0x00, 0xde),
+    "Salmon" => Color(0xff, 0x78, 0x9e),
+    "SeaGreen" => Color(0x4f, 0xff, 0x80),
+    "Sepia" => Color(0x4d, 0x00, 0x00),
+    "SkyBlue" => Color(0x61, 0xff, 0xe0),
+    "SpringGreen" => Color(0xbd, 0xff, 0x3d),
+    "Tan" => Color(0xdb, 0x94, 0x70),
+    "TealBlue" => Color(0x1f, 0xfa, 0xa3),
+    "Thistle" => Color(0xe0, 0x69, 0xff),
+    "Turquoise" => Color(0x26, 0xff, 0xcc),
+    "Violet" => Color(0x36, 0x1f, 0xff),
+    "VioletRed" => Color(0xff, 0x30, 0xff),
+    "White" => Color(0xff, 0xff, 0xff),
+    "WildStrawberry" => Color(0xff, 0x0a, 0x9c),
+    "Yellow" => Color(0xff, 0xff, 0x00),
+    "YellowGreen" => Color(0x8f, 0xff, 0x42),
+    "YellowOrange" => Color(0xff, 0x94, 0x00),
+};
+
+pub(crate) fn get_named_color(name: &str) -> Option<Color> {
+    NAMED_COLORS
+        .get(name)
+        .or_else(|| DVIPS_COLORS.get(name))
+        .copied()
+}
+
+/// Parse a color expression like `red`, `red!50` or `red!30!blue`.
+///
+/// `lookup` resolves the names in the expression, so that colors defined with
+/// `\definecolor` can be used as well.
+pub(crate) fn parse_color_expression(
+    expr: &str,
+    lookup: impl Fn(&str) -> Option<Color>,
+) -> Option<Color> {
+    let mut parts = expr.split('!').map(str::trim);
+    let mut color = lookup(parts.next()?)?;
+    while let Some(percent) = parts.next() {
+        let percent = percent.parse::<f32>().ok()?;
+        // If no second color is given, the color is mixed with white.
+        let other = match parts.next() {
+            Some(name) => lookup(name)?,
+            None => WHITE,
+        };
+        color = color.mix(other, percent);
+    }
+    Some(color)
+}
+
+/// Parse a color specification in the given color model, like `{rgb}{1,0.5,0}`.
+pub(crate) fn parse_color_model(model: &str, spec: &str) -> Option<Color> {
+    let spec = spec.trim();
+    match model.trim() {
+        "rgb" => {
+            let [r, g, b] = parse_components(spec)?;
+            Some(Color(from_unit(r)?, from_unit(g)?, from_unit(b)?))
+        }
+        "RGB" => {
+            let [r, g, b] = parse_components(spec)?;
+            Some(Color(from_byte(r)?, from_byte(g)?, from_byte(b)?))
+        }
+        "HTML" => {
+            if spec.len() != 6 || !spec.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            let value = u32::from_str_radix(spec, 16).ok()?;
+            Some(Color((value >> 16) as u8, (value >> 8) as u8, value as u8))
+        }
+        "gray" => {
+            let gray = from_unit(spec.parse().ok()?)?;
+            Some(Color(gray, gray, gray))
+        }
+        _ => None,
+    }
+}
+
+fn parse_components(spec: &str) -> Option<[f32; 3]> {
+    let mut components = spec.split(',').map(|c| c.trim().parse::<f32>());
+    let result = [
+        components.next()?.ok()?,
+        components.next()?.ok()?,
+        components.next()?.ok()?,
+    ];
+    components.next().is_none().then_some(result)
+}
+
+fn from_unit(value: f32) -> Option<u8> {
+    (0.0..=1.0)
+        .contains(&value)
+        .then(|| (value * 255.0).round() as u8)
+}
+
+fn from_byte(value: f32) -> Option<u8> {
+    (0.0..=255.0).contains(&value).then(|| value.round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(color: Color) -> String {
+        color.hex().iter().collect()
+    }
+
+    #[test]
+    fn color_test() {
+        let problems = [
+            ("red", "#ff0000"),
+            ("red!50", "#ff8080"),
+            ("red!30!blue", "#4d00b3"),
+            ("red!50!blue!50", "#c080c0"),
+            ("green!100", "#00ff00"),
+            ("orange!0!black", "#000000"),
+            ("LimeGreen", "#80ff00"),
+            ("Blue!50", "#8080ff"),
+        ];
+        for (expr, answer) in problems.into_iter() {
+            let color = parse_color_expression(expr, get_named_color).unwrap();
+            assert_eq!(hex(color), answer, "expression: {}", expr);
+        }
+        assert!(parse_color_expression("rot", get_named_color).is_none());
+        assert!(parse_color_expression("red!x", get_named_color).is_none());
+    }
+
+    #[test]
+    fn color_model_test() {
+        let problems = [
+            ("rgb", "1,0.5,0", "#ff8000"),
+            ("RGB", "0, 128, 255", "#0080ff"),
+            ("HTML", "1A2B3c", "#1a2b3c"),
+            ("gray", "0.25", "#404040"),
+        ];
+        for (model, spec, answer) in problems.into_iter() {
+            let color = parse_color_model(model, spec).unwrap();
+            assert_eq!(hex(color), answer, "model: {}, spec: {}", model, spec);
+        }
+        assert!(parse_color_model("rgb", "1,0").is_none());
+        assert!(parse_color_model("rgb", "2,0,0").is_none());
+        assert!(parse_color_model("cmyk", "0,0,0,1").is_none());
+    }
+}
@@ -1,17 +1,20 @@
-use crate::attribute::{FracAttr, MathVariant, OpAttr, ParenAttr, Stretchy, Style, TextTransform};
+use crate::attribute::{
+    FracAttr, HtmlAttr, Limits, LineStyle, MathClass, MathVariant, Notation, OpAttr, Padding,
+    ParenAttr, Stretchy, Style, TextTransform,
+};
 use crate::ops::{self, Op};
 use crate::token::Token;
 
 static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     " " => Token::SingleSpace,
-    "!" => Token::Space("-0.1667"),
+    "!" => Token::Space("-0.1667em"),
     "#" => Token::Letter('#'),
     "$" => Token::Letter('$'),
     "%" => Token::Letter('%'),
     "&" => Token::OpAmpersand,
-    "," => Token::Space("0.1667"),
-    ":" => Token::Space("0.2222"),
-    ";" => Token::Space("0.2778"),
+    "," => Token::Space("0.1667em"),
+    ":" => Token::Space("0.2222em"),
+    ";" => Token::Space("0.2778em"),
     "\\" => Token::NewLine,
     "_" => Token::Letter('_'),
     "AA" => Token::Letter('Å'), // TODO: valid in text mode only
@@ -34,6 +37,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "Cup" => Token::Operator(ops::DOUBLE_UNION),
     "DH" => Token::Letter('Ð'),
     "Dagger" => Token::Letter('‡'),
+    "DeclareMathOperator" => Token::DeclareMathOperator,
     "Delta" => Token::UprightLetter('Δ'),
     "Diamond" => Token::Letter('◊'),
     "Doteq" => Token::Operator(ops::GEOMETRICALLY_EQUAL_TO),
@@ -74,6 +78,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "Rrightarrow" => Token::Operator(ops::RIGHTWARDS_TRIPLE_ARROW),
     "Rsh" => Token::Operator(ops::UPWARDS_ARROW_WITH_TIP_RIGHTWARDS),
     "S" => Token::Letter('§'),
+    "SI" => Token::Quantity,
     "Sigma" => Token::UprightLetter('Σ'),
     "Subset" => Token::Operator(ops::DOUBLE_SUBSET),
     "Supset" => Token::Operator(ops::DOUBLE_SUPERSET),
@@ -90,11 +95,13 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "Yright" => Token::Operator(ops::RIGHTWARDS_ARROW_TAIL),
     "Zeta" => Token::UprightLetter('Ζ'),
     "a" => Token::Letter('å'),
+    "abs" => Token::Delimited(ops::VERTICAL_LINE, ops::VERTICAL_LINE),
     "acute" => Token::OverUnder(ops::ACUTE_ACCENT, true, None),
     "ae" => Token::Letter('æ'),
     "aleph" => Token::Letter('ℵ'),
     "alpha" => Token::Letter('α'),
     "amalg" => Token::Operator(ops::AMALGAMATION_OR_COPRODUCT),
+    "ang" => Token::Angle,
     "angle" => Token::Letter(ops::ANGLE),
     "approx" => Token::Operator(ops::ALMOST_EQUAL_TO),
     "approxeq" => Token::Operator(ops::ALMOST_EQUAL_OR_EQUAL_TO),
@@ -115,10 +122,13 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "backslash" => Token::Paren(ops::REVERSE_SOLIDUS, Some(ParenAttr::Ordinary), Stretchy::Never),
     "bar" => Token::OverUnder(ops::MACRON, true, Some(OpAttr::StretchyFalse)),
     "barwedge" => Token::Operator(ops::NAND),
+    "bbox" => Token::BBox,
+    "bcancel" => Token::Enclose(Notation::DownDiagonalStrike),
     "because" => Token::Operator(ops::BECAUSE),
     "begin" => Token::Begin,
     "beta" => Token::Letter('β'),
     "beth" => Token::Letter('ℶ'),
+    "bf" => Token::FontSwitch(Some(TextTransform::Bold), None),
     "big" => Token::Big("1.2em"),
     "bigcap" => Token::BigOp(ops::N_ARY_INTERSECTION),
     "bigcirc" => Token::Operator(ops::LARGE_CIRCLE),
@@ -145,24 +155,34 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "blacklozenge" => Token::Letter(ops::BLACK_LOZENGE),
     "blacksquare" => Token::Letter(ops::BLACK_SQUARE),
     "bm" => Token::Transform(Some(TextTransform::BoldItalic), None),
+    "bmod" => Token::Bmod,
+    "boldmath" => Token::FontSwitch(Some(TextTransform::BoldItalic), None),
     "boldsymbol" => Token::Transform(Some(TextTransform::BoldItalic), None),
     "bot" => Token::Operator(ops::UP_TACK),
     "botdoteq" => Token::Operator(ops::EQUALS_SIGN_WITH_DOT_BELOW),
     "boxbox" => Token::Operator(ops::SQUARED_SQUARE),
     "boxbslash" => Token::Operator(ops::SQUARED_FALLING_DIAGONAL_SLASH),
     "boxdot" => Token::Operator(ops::SQUARED_DOT_OPERATOR),
+    "boxed" => Token::Boxed,
     "boxminus" => Token::Operator(ops::SQUARED_MINUS),
     "boxplus" => Token::Operator(ops::SQUARED_PLUS),
     "boxslash" => Token::Operator(ops::SQUARED_RISING_DIAGONAL_SLASH),
     "boxtimes" => Token::Operator(ops::SQUARED_TIMES),
+    "bra" => Token::BraKet(ops::MATHEMATICAL_LEFT_ANGLE_BRACKET, ops::VERTICAL_LINE),
+    "braket" => Token::BraKet(ops::MATHEMATICAL_LEFT_ANGLE_BRACKET, ops::MATHEMATICAL_RIGHT_ANGLE_BRACKET),
     "breve" => Token::OverUnder(ops::BREVE, true, None),
     "bullet" => Token::Operator(ops::BULLET_OPERATOR),
     "bumpeq" => Token::Operator(ops::DIFFERENCE_BETWEEN),
+    "cal" => Token::FontSwitch(Some(TextTransform::Script), None),
+    "cancel" => Token::Enclose(Notation::UpDiagonalStrike),
+    "cancelto" => Token::CancelTo,
     "cap" => Token::Operator(ops::INTERSECTION),
     "cdot" => Token::Operator(ops::MIDDLE_DOT),
     "cdots" => Token::Operator(ops::MIDLINE_HORIZONTAL_ELLIPSIS),
+    "ce" => Token::Chemistry(false),
     "centerdot" => Token::Operator(ops::BULLET_OPERATOR),
     "cfrac" => Token::Frac(Some(FracAttr::CFracStyle)),
+    "char" => Token::Char,
     "check" => Token::OverUnder(ops::CARON, true, Some(OpAttr::StretchyFalse)),
     "checkmark" => Token::Letter('✓'),
     "chi" => Token::Letter('χ'),
@@ -176,9 +196,13 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "circledcirc" => Token::Operator(ops::CIRCLED_RING_OPERATOR),
     "circleddash" => Token::Operator(ops::CIRCLED_DASH),
     "cirfnint" => Token::Operator(ops::CIRCULATION_FUNCTION),
+    "clap" => Token::Padded(Padding::CenterOverlap),
+    "cline" => Token::CLine,
     "clubsuit" => Token::Letter('♣'),
     "colon" => Token::Letter(':'),
     "coloneq" => Token::Operator(ops::COLON_EQUALS),
+    "color" => Token::Color,
+    "colorbox" => Token::ColorBox,
     "complement" => Token::Letter(ops::COMPLEMENT),
     "cong" => Token::Operator(ops::APPROXIMATELY_EQUAL_TO),
     "coprod" => Token::BigOp(ops::N_ARY_COPRODUCT),
@@ -205,8 +229,11 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "ddagger" => Token::Letter('‡'),
     "ddot" => Token::OverUnder(ops::DIAERESIS, true, None),
     "ddots" => Token::Operator(ops::DOWN_RIGHT_DIAGONAL_ELLIPSIS),
+    "def" => Token::Def,
+    "definecolor" => Token::DefineColor,
     "deg" => Token::Function("deg"),
     "delta" => Token::Letter('δ'),
+    "derivative" => Token::Derivative(false),
     "det" => Token::Function("det"),
     "dfrac" => Token::Frac(Some(FracAttr::DisplayStyleTrue)),
     "dh" => Token::Letter('ð'),
@@ -214,6 +241,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "diamondsuit" => Token::Letter('♢'),
     "digamma" => Token::Letter('ϝ'),
     "dim" => Token::Function("dim"),
+    "displaylimits" => Token::Limits(Limits::Display),
     "displaystyle" => Token::Style(Style::DisplayStyle),
     "div" => Token::Operator(ops::DIVISION_SIGN),
     "divideontimes" => Token::Operator(ops::DIVISION_TIMES),
@@ -229,11 +257,13 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "downharpoonleft" => Token::Operator(ops::DOWNWARDS_HARPOON_WITH_BARB_LEFTWARDS),
     "downharpoonright" => Token::Operator(ops::DOWNWARDS_HARPOON_WITH_BARB_RIGHTWARDS),
     "dprime" => Token::Operator(ops::DOUBLE_PRIME),
+    "dv" => Token::Derivative(false),
     "earth" => Token::Letter('♁'),
     "ell" => Token::Letter('ℓ'),
     "empty" => Token::Letter(ops::EMPTY_SET),
     "emptyset" => Token::Letter(ops::EMPTY_SET),
     "end" => Token::End,
+    "enspace" => Token::Space("0.5em"),
     "epsilon" => Token::Letter('ϵ'),
     "eqcirc" => Token::Operator(ops::RING_IN_EQUAL_TO),
     "eqcolon" => Token::Operator(ops::EQUALS_COLON),
@@ -247,13 +277,18 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "eta" => Token::Letter('η'),
     "eth" => Token::Letter('ð'),
     "euro" => Token::Letter('€'),
+    "eval" => Token::Evaluated,
+    "evaluated" => Token::Evaluated,
     "exists" => Token::Operator(ops::THERE_EXISTS),
     "exp" => Token::Function("exp"),
     "fallingdotseq" => Token::Operator(ops::APPROXIMATELY_EQUAL_TO_OR_THE_IMAGE_OF),
+    "fbox" => Token::FBox,
+    "fcolorbox" => Token::FColorBox,
     "fint" => Token::Operator(ops::INTEGRAL_AVERAGE_WITH_SLASH),
     "flat" => Token::Letter('♭'),
     "forall" => Token::Operator(ops::FOR_ALL),
     "frac" => Token::Frac(None),
+    "framebox" => Token::FrameBox,
     "frown" => Token::Operator(ops::FROWN),
     "gamma" => Token::Letter('γ'),
     "gcd" => Token::Function("gcd"),
@@ -274,11 +309,21 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "gtrsim" => Token::Operator(ops::GREATER_THAN_OR_EQUIVALENT_TO),
     "hat" => Token::OverUnder(ops::CIRCUMFLEX_ACCENT, true, Some(OpAttr::StretchyFalse)),
     "hbar" => Token::Letter('ℏ'),
+    "hdashline" => Token::HorizontalRule(LineStyle::Dashed),
     "heartsuit" => Token::Letter('♡'),
+    "hline" => Token::HorizontalRule(LineStyle::Solid),
     "hom" => Token::Function("hom"),
     "hookleftarrow" => Token::Operator(ops::LEFTWARDS_ARROW_WITH_HOOK),
     "hookrightarrow" => Token::Operator(ops::RIGHTWARDS_ARROW_WITH_HOOK),
+    "hphantom" => Token::Padded(Padding::HPhantom),
+    "href" => Token::Href,
+    "hskip" => Token::Skip,
     "hslash" => Token::Letter('ℏ'),
+    "hspace" => Token::Skip,
+    "htmlClass" => Token::Html(HtmlAttr::Class),
+    "htmlData" => Token::Html(HtmlAttr::Data),
+    "htmlId" => Token::Html(HtmlAttr::Id),
+    "htmlStyle" => Token::Html(HtmlAttr::Style),
     "iff" => Token::Operator(ops::LONG_LEFT_RIGHT_DOUBLE_ARROW),
     "iiiint" => Token::Integral(ops::QUADRUPLE_INTEGRAL_OPERATOR),
     "iiint" => Token::Integral(ops::TRIPLE_INTEGRAL),
@@ -295,13 +340,17 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "intclockwise" => Token::Operator(ops::CLOCKWISE_INTEGRAL),
     "intercal" => Token::Operator(ops::INTERCALATE),
     "iota" => Token::Letter('ι'),
+    "it" => Token::FontSwitch(Some(TextTransform::Italic), None),
     "jmath" => Token::Letter('ȷ'),
     "jupiter" => Token::Letter('♃'),
     "kappa" => Token::Letter('κ'),
     "ker" => Token::Function("ker"),
+    "kern" => Token::Kern,
     "kernelcontraction" => Token::Operator(ops::HOMOTHETIC),
+    "ket" => Token::BraKet(ops::VERTICAL_LINE, ops::MATHEMATICAL_RIGHT_ANGLE_BRACKET),
     "l" => Token::Letter('ł'),
     "lVert" => Token::Paren(ops::DOUBLE_VERTICAL_LINE, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
+    "label" => Token::Label,
     "lambda" => Token::Letter('λ'),
     "land" => Token::Operator(ops::LOGICAL_AND),
     "langle" => Token::Paren(ops::MATHEMATICAL_LEFT_ANGLE_BRACKET, None, Stretchy::Always),
@@ -337,9 +386,10 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "lightning" => Token::Operator(ops::DOWNWARDS_ZIGZAG_ARROW),
     "lim" => Token::Lim("lim"),
     "liminf" => Token::Lim("lim inf"),
-    "limits" => Token::Limits,
+    "limits" => Token::Limits(Limits::Always),
     "limsup" => Token::Lim("lim sup"),
     "ll" => Token::Operator(ops::MUCH_LESS_THAN),
+    "llap" => Token::Padded(Padding::LeftOverlap),
     "llbracket" => Token::Paren(ops::MATHEMATICAL_LEFT_WHITE_SQUARE_BRACKET, None, Stretchy::Always),
     "llcorner" => Token::Letter(ops::BOTTOM_LEFT_CORNER),
     "lll" => Token::Operator(ops::VERY_MUCH_LESS_THAN),
@@ -365,9 +415,20 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "mars" => Token::Letter('♂'),
     "mathbb" => Token::Transform(Some(TextTransform::DoubleStruck), None),
     "mathbf" => Token::Transform(Some(TextTransform::Bold), None),
+    "mathbin" => Token::Class(MathClass::Bin),
     "mathcal" => Token::Transform(Some(TextTransform::Script), None),
+    "mathclap" => Token::Padded(Padding::CenterOverlap),
+    "mathclose" => Token::Class(MathClass::Close),
     "mathfrak" => Token::Transform(Some(TextTransform::Fraktur), None),
+    "mathinner" => Token::Class(MathClass::Inner),
     "mathit" => Token::Transform(Some(TextTransform::Italic), None),
+    "mathllap" => Token::Padded(Padding::LeftOverlap),
+    "mathop" => Token::Class(MathClass::Op),
+    "mathopen" => Token::Class(MathClass::Open),
+    "mathord" => Token::Class(MathClass::Ord),
+    "mathpunct" => Token::Class(MathClass::Punct),
+    "mathrel" => Token::Class(MathClass::Rel),
+    "mathrlap" => Token::Padded(Padding::RightOverlap),
     "mathrm" => Token::Transform(None, Some(MathVariant::Normal)),
     "mathscr" => Token::Transform(Some(TextTransform::Script), None),
     "mathsf" => Token::Transform(Some(TextTransform::SansSerif), None),
@@ -376,13 +437,19 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "max" => Token::Lim("max"),
     "measeq" => Token::Operator(ops::MEASURED_BY), // from "stix"
     "measuredangle" => Token::Letter(ops::MEASURED_ANGLE),
+    "medspace" => Token::Space("0.2222em"),
     "mercury" => Token::Letter('☿'),
     "mho" => Token::Letter('℧'),
     "mid" => Token::Operator(ops::DIVIDES),
     "middle" => Token::Middle,
     "min" => Token::Lim("min"),
+    "mit" => Token::FontSwitch(Some(TextTransform::Italic), None),
+    "mkern" => Token::MathKern,
+    "mod" => Token::Mod,
     "models" => Token::Operator(ops::TRUE),
     "mp" => Token::Operator(ops::MINUS_OR_PLUS_SIGN),
+    "mskip" => Token::MathSkip,
+    "mspace" => Token::MathSkip,
     "mu" => Token::Letter('μ'),
     "multimap" => Token::Operator(ops::MULTIMAP),
     "nLeftarrow" => Token::Operator(ops::LEFTWARDS_DOUBLE_ARROW_WITH_STROKE),
@@ -393,9 +460,13 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "ne" => Token::Operator(ops::NOT_EQUAL_TO),
     "nearrow" => Token::Operator(ops::NORTH_EAST_ARROW),
     "neg" => Token::Operator(ops::NOT_SIGN),
+    "negmedspace" => Token::Space("-0.2222em"),
+    "negthickspace" => Token::Space("-0.2778em"),
+    "negthinspace" => Token::Space("-0.1667em"),
     "neptune" => Token::Letter('♆'),
     "neq" => Token::Operator(ops::NOT_EQUAL_TO),
     "nequiv" => Token::Operator(ops::NOT_IDENTICAL_TO),
+    "newcommand" => Token::NewCommand,
     "nexists" => Token::Operator(ops::THERE_DOES_NOT_EXIST),
     "ng" => Token::Letter('ŋ'),
     "ngeq" => Token::Operator(ops::NEITHER_GREATER_THAN_NOR_EQUAL_TO),
@@ -410,7 +481,11 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "nlessgt" => Token::Operator(ops::NEITHER_LESS_THAN_NOR_GREATER_THAN),
     "nlesssim" => Token::Operator(ops::NEITHER_LESS_THAN_NOR_EQUIVALENT_TO),
     "nmid" => Token::Operator(ops::DOES_NOT_DIVIDE),
+    "nolimits" => Token::Limits(Limits::Never),
+    "nonumber" => Token::NoTag,
+    "norm" => Token::Delimited(ops::DOUBLE_VERTICAL_LINE, ops::DOUBLE_VERTICAL_LINE),
     "not" => Token::Not,
+    "notag" => Token::NoTag,
     "notin" => Token::Operator(ops::NOT_AN_ELEMENT_OF),
     "nparallel" => Token::Operator(ops::NOT_PARALLEL_TO),
     "nprec" => Token::Operator(ops::DOES_NOT_PRECEDE),
@@ -424,6 +499,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "nsupset" => Token::Operator(ops::NOT_A_SUPERSET_OF),
     "nsupseteq" => Token::Operator(ops::NEITHER_A_SUPERSET_OF_NOR_EQUAL_TO),
     "nu" => Token::Letter('ν'),
+    "num" => Token::Num,
     "nwarrow" => Token::Operator(ops::NORTH_WEST_ARROW),
     "o" => Token::Letter('ø'),
     "odot" => Token::Operator(ops::CIRCLED_DOT_OPERATOR),
@@ -435,8 +511,10 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "omega" => Token::Letter('ω'),
     "omicron" => Token::Letter('ο'),
     "ominus" => Token::Operator(ops::CIRCLED_MINUS),
-    "operatorname" => Token::OperatorName,
+    "operatorname" => Token::OperatorName(false),
+    "operatornamewithlimits" => Token::OperatorName(true),
     "oplus" => Token::Operator(ops::CIRCLED_PLUS),
+    "order" => Token::Order,
     "oslash" => Token::Operator(ops::CIRCLED_DIVISION_SLASH),
     "otimes" => Token::Operator(ops::CIRCLED_TIMES),
     "overbrace" => Token::OverUnderBrace(ops::TOP_CURLY_BRACKET, true),
@@ -446,12 +524,18 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "overparen" => Token::OverUnderBrace(ops::TOP_PARENTHESIS, true),
     "overrightarrow" => Token::OverUnder(ops::RIGHTWARDS_ARROW, true, None),
     "overset" => Token::Overset,
+    "overunderset" => Token::OverUnderset,
     "parallel" => Token::Operator(ops::PARALLEL_TO),
     "partial" => Token::Letter(ops::PARTIAL_DIFFERENTIAL),
+    "partialderivative" => Token::Derivative(true),
+    "pdv" => Token::Derivative(true),
     "perp" => Token::Operator(ops::UP_TACK),
+    "phantom" => Token::Padded(Padding::Phantom),
     "phi" => Token::Letter('ϕ'),
     "pi" => Token::Letter('π'),
     "pm" => Token::Operator(ops::PLUS_MINUS_SIGN),
+    "pmod" => Token::Pmod,
+    "pod" => Token::Pod,
     "pounds" => Token::Letter('£'),
     "prec" => Token::Operator(ops::PRECEDES),
     "precapprox" => Token::Operator(ops::PRECEDES_ABOVE_ALMOST_EQUAL_TO),
@@ -461,19 +545,23 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "precneqq" => Token::Operator(ops::PRECEDES_ABOVE_NOT_EQUAL_TO),
     "precnsim" => Token::Operator(ops::PRECEDES_BUT_NOT_EQUIVALENT_TO),
     "precsim" => Token::Operator(ops::PRECEDES_OR_EQUIVALENT_TO),
+    "prescript" => Token::Prescript,
     "prime" => Token::Operator(ops::PRIME),
     "prod" => Token::BigOp(ops::N_ARY_PRODUCT),
     "propto" => Token::Operator(ops::PROPORTIONAL_TO),
     "psi" => Token::Letter('ψ'),
+    "pu" => Token::Chemistry(true),
     "qprime" => Token::Operator(ops::QUADRUPLE_PRIME),
-    "qquad" => Token::Space("2"),
-    "quad" => Token::Space("1"),
+    "qquad" => Token::Space("2em"),
+    "qty" => Token::Quantity,
+    "quad" => Token::Space("1em"),
     "questeq" => Token::Operator(ops::QUESTIONED_EQUAL_TO), // from "stix"
     "rVert" => Token::Paren(ops::DOUBLE_VERTICAL_LINE, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
     "rangle" => Token::Paren(ops::MATHEMATICAL_RIGHT_ANGLE_BRACKET, None, Stretchy::Always),
     "rbrace" => Token::Paren(ops::RIGHT_CURLY_BRACKET, None, Stretchy::Always),
     "rbrack" => Token::Paren(ops::RIGHT_SQUARE_BRACKET, None, Stretchy::Always),
     "rceil" => Token::Paren(ops::RIGHT_CEILING, None, Stretchy::Always),
+    "renewcommand" => Token::RenewCommand,
     "rfloor" => Token::Paren(ops::RIGHT_FLOOR, None, Stretchy::Always),
     "rgroup" => Token::Paren(ops::MATHEMATICAL_RIGHT_FLATTENED_PARENTHESIS, None, Stretchy::Always),
     "rhd" => Token::Operator(ops::CONTAINS_AS_NORMAL_SUBGROUP),
@@ -489,9 +577,12 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "rightsquigarrow" => Token::Operator(ops::RIGHTWARDS_SQUIGGLE_ARROW),
     "rightthreetimes" => Token::Operator(ops::RIGHT_SEMIDIRECT_PRODUCT),
     "risingdotseq" => Token::Operator(ops::IMAGE_OF_OR_APPROXIMATELY_EQUAL_TO),
+    "rlap" => Token::Padded(Padding::RightOverlap),
+    "rm" => Token::FontSwitch(None, Some(MathVariant::Normal)),
     "rq" => Token::Letter('’'),
     "rrbracket" => Token::Paren(ops::MATHEMATICAL_RIGHT_WHITE_SQUARE_BRACKET, None, Stretchy::Always),
     "rtimes" => Token::Operator(ops::RIGHT_NORMAL_FACTOR_SEMIDIRECT_PRODUCT),
+    "rule" => Token::Rule,
     "rupee" => Token::Letter('₹'),
     "rvert" => Token::Paren(ops::VERTICAL_LINE, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
     "saturn" => Token::Letter('♄'),
@@ -500,16 +591,22 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "searrow" => Token::Operator(ops::SOUTH_EAST_ARROW),
     "sec" => Token::Function("sec"),
     "setminus" => Token::Operator(ops::SET_MINUS),
+    "sf" => Token::FontSwitch(Some(TextTransform::SansSerif), None),
     "sgn" => Token::Function("sgn"),
     "sharp" => Token::Letter('♯'),
+    "si" => Token::Unit,
+    "sideset" => Token::Sideset,
     "sigma" => Token::Letter('σ'),
     "sim" => Token::Operator(ops::TILDE_OPERATOR),
     "simeq" => Token::Operator(ops::ASYMPTOTICALLY_EQUAL_TO),
     "sin" => Token::Function("sin"),
     "sinh" => Token::Function("sinh"),
+    "sisetup" => Token::UnitSetup,
     "slashed" => Token::Slashed,
     "smallsetminus" => Token::Operator(ops::SMALL_REVERSE_SOLIDUS),
+    "smash" => Token::Smash,
     "smile" => Token::Operator(ops::SMILE),
+    "sout" => Token::Enclose(Notation::HorizontalStrike),
     "spadesuit" => Token::Letter('♠'),
     "sphericalangle" => Token::Letter(ops::SPHERICAL_ANGLE),
     "sqcap" => Token::Operator(ops::SQUARE_CAP),
@@ -521,8 +618,11 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "sqsupseteq" => Token::Operator(ops::SQUARE_ORIGINAL_OF_OR_EQUAL_TO),
     "square" => Token::Letter('□'),
     "ss" => Token::Letter('ß'),
+    "stackbin" => Token::Stackrel(MathClass::Bin),
+    "stackrel" => Token::Stackrel(MathClass::Rel),
     "star" => Token::Operator(ops::STAR_OPERATOR),
     "stareq" => Token::Operator(ops::STAR_EQUALS), // from "stix"
+    "strut" => Token::Strut,
     "subset" => Token::Operator(ops::SUBSET_OF),
     "subseteq" => Token::Operator(ops::SUBSET_OF_OR_EQUAL_TO),
     "subsetneq" => Token::Operator(ops::SUBSET_OF_WITH_NOT_EQUAL_TO),
@@ -535,6 +635,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "succneqq" => Token::Operator(ops::SUCCEEDS_ABOVE_NOT_EQUAL_TO),
     "succnsim" => Token::Operator(ops::SUCCEEDS_BUT_NOT_EQUIVALENT_TO),
     "succsim" => Token::Operator(ops::SUCCEEDS_OR_EQUIVALENT_TO),
+    "substack" => Token::SubStack,
     "sum" => Token::BigOp(ops::N_ARY_SUMMATION),
     "sumint" => Token::BigOp(ops::SUMMATION_WITH_INTEGRAL),
     "sun" => Token::Letter('☼'),
@@ -545,12 +646,15 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "supsetneqq" => Token::Operator(ops::SUPERSET_OF_ABOVE_NOT_EQUAL_TO),
     "swarrow" => Token::Operator(ops::SOUTH_WEST_ARROW),
     "symbf" => Token::Transform(Some(TextTransform::BoldItalic), None),
+    "symbol" => Token::Unicode,
+    "tag" => Token::Tag,
     "tan" => Token::Function("tan"),
     "tanh" => Token::Function("tanh"),
     "tau" => Token::Letter('τ'),
     "tbinom" => Token::Binom(Some(FracAttr::DisplayStyleFalse)),
     "text" => Token::Text(None),
     "textbf" => Token::Text(Some(TextTransform::Bold)),
+    "textcolor" => Token::TextColor,
     "textit" => Token::Text(Some(TextTransform::Italic)),
     "textstyle" => Token::Style(Style::TextStyle),
     "texttt" => Token::Text(Some(TextTransform::Monospace)),
@@ -559,6 +663,8 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "th" => Token::Letter('þ'),
     "therefore" => Token::Operator(ops::THEREFORE),
     "theta" => Token::Letter('θ'),
+    "thickspace" => Token::Space("0.2778em"),
+    "thinspace" => Token::Space("0.1667em"),
     "tilde" => Token::OverUnder(ops::TILDE, true, Some(OpAttr::StretchyFalse)),
     "times" => Token::Operator(ops::MULTIPLICATION_SIGN),
     "to" => Token::Operator(ops::RIGHTWARDS_ARROW),
@@ -569,12 +675,16 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "triangleq" => Token::Operator(ops::DELTA_EQUAL_TO),
     "triangleright" => Token::Operator(ops::WHITE_RIGHT_POINTING_TRIANGLE),
     "trprime" => Token::Operator(ops::TRIPLE_PRIME),
+    "tt" => Token::FontSwitch(Some(TextTransform::Monospace), None),
     "ulcorner" => Token::Letter(ops::TOP_LEFT_CORNER),
     "underbrace" => Token::OverUnderBrace(ops::BOTTOM_CURLY_BRACKET, false),
     "underbracket" => Token::OverUnderBrace(ops::BOTTOM_SQUARE_BRACKET, false),
     "underline" => Token::OverUnder(ops::LOW_LINE, false, None),
+    "underoverset" => Token::UnderOverset,
     "underparen" => Token::OverUnderBrace(ops::BOTTOM_PARENTHESIS, false),
     "underset" => Token::Underset,
+    "unicode" => Token::Unicode,
+    "unit" => Token::Unit,
     "unlhd" => Token::Operator(ops::NORMAL_SUBGROUP_OF_OR_EQUAL_TO),
     "unrhd" => Token::Operator(ops::CONTAINS_AS_NORMAL_SUBGROUP_OR_EQUAL_TO),
     "uparrow" => Token::Paren(ops::UPWARDS_ARROW, None, Stretchy::Inconsistent),
@@ -586,6 +696,7 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "upuparrows" => Token::Operator(ops::UPWARDS_PAIRED_ARROWS),
     "uranus" => Token::Letter('♅'),
     "urcorner" => Token::Letter(ops::TOP_RIGHT_CORNER),
+    "url" => Token::Url,
     "vDash" => Token::Operator(ops::TRUE),
     "varDelta" => Token::Letter('Δ'), // not italicized
     "varGamma" => Token::Letter('Γ'), // not italicized
@@ -607,21 +718,40 @@ static COMMANDS: phf::Map<&'static str, Token> = phf::phf_map! {
     "varsigma" => Token::Letter('ς'),
     "vartheta" => Token::Letter('ϑ'),
     "vartriangle" => Token::Operator(ops::WHITE_UP_POINTING_TRIANGLE),
+    "vb" => Token::VectorBold,
     "vdash" => Token::Operator(ops::RIGHT_TACK),
     "vdots" => Token::Operator(ops::VERTICAL_ELLIPSIS),
     "vec" => Token::OverUnder(ops::RIGHTWARDS_ARROW, true, Some(OpAttr::StretchyFalse)),
+    "vectorbold" => Token::VectorBold,
     "vee" => Token::Operator(ops::LOGICAL_OR),
     "veebar" => Token::Operator(ops::XOR),
     "veeeq" => Token::Operator(ops::EQUIANGULAR_TO), // from "stix"
     "venus" => Token::Letter('♀'),
     "vert" => Token::Paren(ops::VERTICAL_LINE, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
+    "vphantom" => Token::Padded(Padding::VPhantom),
     "wedge" => Token::Operator(ops::LOGICAL_AND),
     "wedgeq" => Token::Operator(ops::ESTIMATES), // from "stix"
     "widehat" => Token::OverUnder(ops::CIRCUMFLEX_ACCENT, true, None),
     "widetilde" => Token::OverUnder(ops::TILDE, true, None),
     "wp" => Token::Function("℘"),
     "wr" => Token::Operator(ops::WREATH_PRODUCT),
+    "xLeftarrow" => Token::XArrow(ops::LEFTWARDS_DOUBLE_ARROW),
+    "xLeftrightarrow" => Token::XArrow(ops::LEFT_RIGHT_DOUBLE_ARROW),
+    "xRightarrow" => Token::XArrow(ops::RIGHTWARDS_DOUBLE_ARROW),
+    "xcancel" => Token::Enclose(Notation::CrossStrike),
+    "xhookleftarrow" => Token::XArrow(ops::LEFTWARDS_ARROW_WITH_HOOK),
+    "xhookrightarrow" => Token::XArrow(ops::RIGHTWARDS_ARROW_WITH_HOOK),
     "xi" => Token::Letter('ξ'),
+    "xleftarrow" => Token::XArrow(ops::LEFTWARDS_ARROW),
+    "xleftharpoondown" => Token::XArrow(ops::LEFTWARDS_HARPOON_WITH_BARB_DOWNWARDS),
+    "xleftharpoonup" => Token::XArrow(ops::LEFTWARDS_HARPOON_WITH_BARB_UPWARDS),
+    "xleftrightarrow" => Token::XArrow(ops::LEFT_RIGHT_ARROW),
+    "xleftrightharpoons" => Token::XArrow(ops::LEFTWARDS_HARPOON_OVER_RIGHTWARDS_HARPOON),
+    "xmapsto" => Token::XArrow(ops::RIGHTWARDS_ARROW_FROM_BAR),
+    "xrightarrow" => Token::XArrow(ops::RIGHTWARDS_ARROW),
+    "xrightharpoondown" => Token::XArrow(ops::RIGHTWARDS_HARPOON_WITH_BARB_DOWNWARDS),
+    "xrightharpoonup" => Token::XArrow(ops::RIGHTWARDS_HARPOON_WITH_BARB_UPWARDS),
+    "xrightleftharpoons" => Token::XArrow(ops::RIGHTWARDS_HARPOON_OVER_LEFTWARDS_HARPOON),
     "zeta" => Token::Letter('ζ'),
     "{" => Token::Paren(ops::LEFT_CURLY_BRACKET, None, Stretchy::Always),
     "|" => Token::Paren(ops::DOUBLE_VERTICAL_LINE, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
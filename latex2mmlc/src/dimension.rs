@@ -0,0 +1,132 @@
+//! Dimensions
+//!
+//! The dimensions of the spacing commands like `\kern` or `\hspace` are converted
+//! to CSS lengths. TeX's `pt` is slightly smaller than the CSS `pt` (1/72.27 inch
+//! instead of 1/72 inch), but the difference is ignored, so that `pt` can be passed
+//! through and the other units are converted relative to it.
+
+/// Parse a dimension like `-3pt` or `0.5 em`, which may be followed by glue like
+/// `plus 1fil minus 2pt`. The glue is ignored.
+///
+/// Returns the value and the CSS unit.
+pub(crate) fn parse_dimension(input: &str) -> Option<(f32, &'static str)> {
+    parse_dimension_in(input, false)
+}
+
+/// Parse a dimension of `\mkern`, `\mskip` or `\mspace`, which has to be in math
+/// units like `-2mu plus 1fil`.
+pub(crate) fn parse_math_dimension(input: &str) -> Option<(f32, &'static str)> {
+    parse_dimension_in(input, true)
+}
+
+fn parse_dimension_in(input: &str, math_units: bool) -> Option<(f32, &'static str)> {
+    let (value, unit, rest) = parse_length(input)?;
+    if math_units && unit != "mu" {
+        return None;
+    }
+    let (factor, css_unit) = convert_unit(unit)?;
+    let mut rest = rest.trim_start();
+    for keyword in ["plus", "minus"] {
+        if let Some(glue) = rest.strip_prefix(keyword) {
+            let (_, unit, glue_rest) = parse_length(glue)?;
+            let is_infinite = matches!(unit, "fil" | "fill" | "filll");
+            if !is_infinite && ((math_units && unit != "mu") || convert_unit(unit).is_none()) {
+                return None;
+            }
+            rest = glue_rest.trim_start();
+        }
+    }
+    rest.is_empty().then_some((value * factor, css_unit))
+}
+
+/// Parse a signed number followed by a unit, and return the rest of the input.
+fn parse_length(input: &str) -> Option<(f32, &str, &str)> {
+    let mut negative = false;
+    let input = input.trim_start_matches(|c: char| match c {
+        '-' => {
+            negative = !negative;
+            true
+        }
+        '+' => true,
+        c => c.is_ascii_whitespace(),
+    });
+    let number_end = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+        .unwrap_or(input.len());
+    let (number, rest) = input.split_at(number_end);
+    // TeX also accepts a comma as the decimal separator.
+    let value = match number.replace(',', ".").as_str() {
+        "" => return None,
+        "." => 0.0,
+        number => number.parse::<f32>().ok()?,
+    };
+    let rest = rest.trim_start();
+    // With the keyword `true`, like in `1true in`, the unit is not scaled by TeX's
+    // `\mag`, which we don't support anyway. It only works with absolute units.
+    let (is_true, rest) = match rest.strip_prefix("true") {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, rest),
+    };
+    let unit_end = rest
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(rest.len());
+    let (unit, rest) = rest.split_at(unit_end);
+    if is_true && matches!(unit, "em" | "ex" | "mu") {
+        return None;
+    }
+    Some((if negative { -value } else { value }, unit, rest))
+}
+
+/// Return the factor and the CSS unit for a TeX unit.
+fn convert_unit(unit: &str) -> Option<(f32, &'static str)> {
+    Some(match unit {
+        "em" => (1.0, "em"),
+        "ex" => (1.0, "ex"),
+        "mu" => (1.0 / 18.0, "em"),
+        "pt" => (1.0, "pt"),
+        "bp" => (72.27 / 72.0, "pt"),
+        "dd" => (1238.0 / 1157.0, "pt"),
+        "pc" => (1.0, "pc"),
+        "cm" => (1.0, "cm"),
+        "mm" => (1.0, "mm"),
+        "in" => (1.0, "in"),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimension_test() {
+        let problems = [
+            ("3pt", (3.0, "pt")),
+            ("-2mu", (-2.0 / 18.0, "em")),
+            ("0.5em", (0.5, "em")),
+            (" - -1,5 ex", (1.5, "ex")),
+            (".25in", (0.25, "in")),
+            ("1em plus 2pt minus 1fil", (1.0, "em")),
+            ("10mm plus 1fill", (10.0, "mm")),
+        ];
+        for (input, answer) in problems.into_iter() {
+            assert_eq!(parse_dimension(input), Some(answer), "input: {}", input);
+        }
+        assert!(parse_dimension("3").is_none());
+        assert!(parse_dimension("em").is_none());
+        assert!(parse_dimension("3px").is_none());
+        assert!(parse_dimension("1fil").is_none());
+        assert!(parse_dimension("1em x").is_none());
+        assert!(parse_dimension("1em plus").is_none());
+        assert_eq!(parse_dimension("12true pt"), Some((12.0, "pt")));
+        assert_eq!(parse_dimension("1truein plus 1 true pt"), Some((1.0, "in")));
+        assert!(parse_dimension("1true em").is_none());
+        assert_eq!(
+            parse_math_dimension("-2mu plus 1fil"),
+            Some((-2.0 / 18.0, "em"))
+        );
+        assert!(parse_math_dimension("3pt").is_none());
+        assert!(parse_math_dimension("3mu plus 1pt").is_none());
+        assert!(parse_math_dimension("3true mu").is_none());
+    }
+}
@@ -33,6 +33,31 @@ pub enum LatexErrKind<'source> {
         correct_place: Place,
     },
     ExpectedText(&'static str),
+    InvalidMacroDefinition(&'source str),
+    AlreadyDefined(&'source str),
+    MacroExpansionLimit,
+    UnexpandableParameter(&'source str),
+    UnknownColor(&'source str),
+    InvalidBBoxOption(&'source str),
+    InvalidColumnSpec(&'source str),
+    TooManyColumns(&'source str),
+    InvalidCLine(&'source str),
+    InvalidPosition(&'source str),
+    InvalidBoxPosition(&'source str),
+    InvalidLabel(&'source str),
+    DuplicateLabel(&'source str),
+    MultipleTags,
+    InvalidSmashOption(&'source str),
+    InvalidDimension(&'source str),
+    ExpectedMathUnits(&'source str),
+    InvalidChemistry(&'source str),
+    InvalidNumber(&'source str),
+    UnknownUnit(&'source str),
+    InvalidUnitOption(&'source str),
+    LinkNotAllowed(&'source str),
+    HtmlNotAllowed(&'static str),
+    InvalidHtmlData(&'source str),
+    InvalidCharCode(&'source str),
 }
 
 #[derive(Debug, AsRefStr)]
@@ -44,6 +69,8 @@ pub enum Place {
     BeforeSomeOps,
     #[strum(serialize = r"after an identifier or operator")]
     AfterOpOrIdent,
+    #[strum(serialize = r"in a row of a display environment like equation or align")]
+    InDisplayRow,
 }
 
 impl LatexErrKind<'_> {
@@ -89,6 +116,85 @@ impl LatexErrKind<'_> {
                     + "."
             }
             LatexErrKind::ExpectedText(place) => "Expected text in ".to_string() + place + ".",
+            LatexErrKind::InvalidMacroDefinition(name) => {
+                "Invalid macro definition: \"".to_string() + name + "\"."
+            }
+            LatexErrKind::AlreadyDefined(name) => {
+                "Command \"\\".to_string()
+                    + name
+                    + "\" is already defined; use \\renewcommand to redefine it."
+            }
+            LatexErrKind::MacroExpansionLimit => {
+                "Too many macro expansions; the macro is probably recursive.".to_string()
+            }
+            LatexErrKind::UnexpandableParameter(text) => {
+                "Macro parameters in \"".to_string()
+                    + text
+                    + "\" can't be expanded; they have to make up the whole argument."
+            }
+            LatexErrKind::UnknownColor(color) => "Unknown color \"".to_string() + color + "\".",
+            LatexErrKind::InvalidBBoxOption(option) => {
+                "Invalid option for \\bbox: \"".to_string() + option + "\"."
+            }
+            LatexErrKind::InvalidColumnSpec(env) => {
+                "Invalid column specification for the \"".to_string() + env + "\" environment."
+            }
+            LatexErrKind::TooManyColumns(env) => {
+                "Too many columns in the \"".to_string() + env + "\" environment."
+            }
+            LatexErrKind::InvalidCLine(range) => {
+                "Invalid range for \\cline: \"".to_string() + range + "\"."
+            }
+            LatexErrKind::InvalidBoxPosition(position) => {
+                "Invalid position in \\framebox: \"".to_string()
+                    + position
+                    + "\"; it has to be l, c or r."
+            }
+            LatexErrKind::InvalidPosition(position) => {
+                "Invalid vertical position: \"".to_string()
+                    + position
+                    + "\"; tables are always centered."
+            }
+            LatexErrKind::InvalidLabel(label) => "Invalid label: \"".to_string() + label + "\".",
+            LatexErrKind::DuplicateLabel(label) => {
+                "Duplicate label: \"".to_string() + label + "\"."
+            }
+            LatexErrKind::MultipleTags => "Multiple \\tag in one row.".to_string(),
+            LatexErrKind::InvalidDimension(dimension) => {
+                "Invalid dimension: \"".to_string() + dimension + "\"."
+            }
+            LatexErrKind::ExpectedMathUnits(dimension) => {
+                "Expected a dimension in math units like \"3mu\", but got \"".to_string()
+                    + dimension
+                    + "\"."
+            }
+            LatexErrKind::InvalidChemistry(content) => {
+                "Invalid chemical formula or unit: \"".to_string() + content + "\"."
+            }
+            LatexErrKind::InvalidNumber(number) => {
+                "Invalid number: \"".to_string() + number + "\"."
+            }
+            LatexErrKind::UnknownUnit(unit) => "Unknown unit: \"".to_string() + unit + "\".",
+            LatexErrKind::InvalidUnitOption(option) => {
+                "Invalid unit option: \"".to_string() + option + "\"."
+            }
+            LatexErrKind::LinkNotAllowed(url) => {
+                "The link \"".to_string() + url + "\" is not allowed by the link policy."
+            }
+            LatexErrKind::HtmlNotAllowed(command) => {
+                "\"".to_string()
+                    + command
+                    + "\" is not allowed without the `html_attributes` option."
+            }
+            LatexErrKind::InvalidHtmlData(data) => {
+                "Invalid data attributes: \"".to_string() + data + "\"."
+            }
+            LatexErrKind::InvalidCharCode(code) => {
+                "Invalid character code: \"".to_string() + code + "\"."
+            }
+            LatexErrKind::InvalidSmashOption(option) => {
+                "Invalid option for \\smash: \"".to_string() + option + "\"."
+            }
         }
     }
 }
@@ -12,6 +12,7 @@ use crate::attribute::{ParenAttr, Stretchy};
 use crate::commands::get_command;
 use crate::error::GetUnwrap;
 use crate::token::TokLoc;
+use crate::unicode;
 use crate::{ops, token::Token};
 
 /// Lexer
@@ -137,6 +138,372 @@ impl<'source> Lexer<'source> {
         }
     }
 
+    /// Read everything until the `}` which closes the group, after the opening `{`
+    /// has been consumed.
+    #[inline]
+    pub(crate) fn read_brace_content(&mut self) -> Option<&'source str> {
+        self.read_balanced('}')
+    }
+
+    /// Read text until the next `]`, after the opening `[` has been consumed.
+    #[inline]
+    pub(crate) fn read_bracket_content(&mut self) -> Option<&'source str> {
+        self.read_balanced(']')
+    }
+
+    /// Return the text until the next `]`, after the opening `[` has been consumed,
+    /// without consuming anything.
+    pub(crate) fn peek_bracket_content(&self) -> Option<&'source str> {
+        self.clone().read_balanced(']')
+    }
+
+    /// Return the next character that is not whitespace, without consuming anything.
+    ///
+    /// In text mode, whitespace is significant, so `None` is returned if there is any.
+    fn peek_non_whitespace(&self) -> Option<char> {
+        let mut input = self.input.clone();
+        let mut cur = self.peek.1;
+        while cur.is_ascii_whitespace() {
+            if self.text_mode {
+                return None;
+            }
+            cur = input.next().map_or('\u{0}', |(_, c)| c);
+        }
+        Some(cur)
+    }
+
+    /// Return the name of the command that will be read next, without consuming anything.
+    pub(crate) fn upcoming_command(&self) -> Option<&'source str> {
+        if self.peek_non_whitespace()? != '\\' {
+            return None;
+        }
+        let mut lexer = self.clone();
+        lexer.skip_whitespace();
+        lexer.read_char(); // Discard the backslash.
+        Some(lexer.read_command())
+    }
+
+    /// Returns `true` if there is nothing left in the input except whitespace.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        matches!(self.peek_non_whitespace(), Some('\u{0}'))
+    }
+
+    /// If the next token is a macro parameter like `#1`, consume it and return its number.
+    pub(crate) fn read_macro_param(&mut self) -> Option<usize> {
+        if self.peek_non_whitespace()? != '#' {
+            return None;
+        }
+        let mut lexer = self.clone();
+        lexer.skip_whitespace();
+        lexer.read_char(); // Discard the `#`.
+        let num = lexer.peek.1.to_digit(10).filter(|d| *d > 0)?;
+        lexer.read_char();
+        *self = lexer;
+        Some(num as usize)
+    }
+
+    /// Read everything up to the `close` character which matches the already
+    /// consumed opening character, taking nested braces into account.
+    ///
+    /// Escaped characters are skipped over, so `\}` does not close a group.
+    fn read_balanced(&mut self, close: char) -> Option<&'source str> {
+        let start = self.peek.0;
+        let mut brace_count = 0usize;
+        loop {
+            let (end, cur) = self.read_char();
+            match cur {
+                '\u{0}' => return None,
+                '\\' => {
+                    self.read_char();
+                }
+                '{' => brace_count += 1,
+                '}' if brace_count > 0 => brace_count -= 1,
+                c if c == close && brace_count == 0 => {
+                    return Some(self.input_string.get_unwrap(start..end));
+                }
+                '}' => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Read a mandatory macro argument in its raw form.
+    ///
+    /// This is either the content of a `{...}` group, a single command or a single character.
+    pub(crate) fn read_argument(&mut self) -> Option<&'source str> {
+        self.skip_whitespace();
+        let (start, cur) = self.read_char();
+        match cur {
+            '\u{0}' | '}' => None,
+            '{' => self.read_balanced('}'),
+            '\\' => {
+                self.read_command();
+                Some(self.input_string.get_unwrap(start..self.peek.0))
+            }
+            '#' if self.peek.1.is_ascii_digit() => {
+                // A parameter of the enclosing macro, which is passed on as a whole.
+                self.read_char();
+                Some(self.input_string.get_unwrap(start..self.peek.0))
+            }
+            _ => Some(self.input_string.get_unwrap(start..self.peek.0)),
+        }
+    }
+
+    /// Read the content of a `{...}` group in its raw form.
+    pub(crate) fn read_group(&mut self) -> Option<&'source str> {
+        self.skip_whitespace();
+        if self.read_char().1 != '{' {
+            return None;
+        }
+        self.read_balanced('}')
+    }
+
+    /// Read a `{...}` group in its raw form, including the braces.
+    pub(crate) fn read_braced_group(&mut self) -> Option<&'source str> {
+        self.skip_whitespace();
+        let start = self.peek.0;
+        self.read_group()?;
+        Some(self.input_string.get_unwrap(start..self.peek.0))
+    }
+
+    /// Skip the `*` of a starred command like `\newcommand*`.
+    /// Returns `true` if there was a star.
+    pub(crate) fn skip_star(&mut self) -> bool {
+        if self.peek_non_whitespace() != Some('*') {
+            return false;
+        }
+        self.skip_whitespace();
+        self.read_char();
+        true
+    }
+
+    /// Read an optional argument in square brackets in its raw form.
+    ///
+    /// Returns `Some(None)` if there is no optional argument and `None` if the
+    /// argument is not closed.
+    pub(crate) fn read_optional_argument(&mut self) -> Option<Option<&'source str>> {
+        if self.peek_non_whitespace() != Some('[') {
+            return Some(None);
+        }
+        self.skip_whitespace();
+        self.read_char(); // Discard the `[`.
+        self.read_balanced(']').map(Some)
+    }
+
+    /// Read the name of a command, including the backslash, like in `\newcommand\foo`.
+    /// The name may also be enclosed in braces: `\newcommand{\foo}`.
+    pub(crate) fn read_command_name(&mut self) -> Option<&'source str> {
+        self.skip_whitespace();
+        let braced = self.peek.1 == '{';
+        if braced {
+            self.read_char();
+            self.skip_whitespace();
+        }
+        if self.peek.1 != '\\' {
+            return None;
+        }
+        self.read_char(); // Discard the backslash.
+        let name = self.read_command();
+        if braced {
+            self.skip_whitespace();
+            if self.read_char().1 != '}' {
+                return None;
+            }
+        }
+        Some(name)
+    }
+
+    /// Read the parameter text of a `\def`, like `#1#2`, and return the number of parameters.
+    pub(crate) fn read_def_params(&mut self) -> Option<usize> {
+        let mut count = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek.1 {
+                '{' => return Some(count),
+                '#' => {
+                    self.read_char();
+                    // Parameters have to be numbered consecutively, starting with 1.
+                    if self.read_char().1.to_digit(10)? as usize != count + 1 {
+                        return None;
+                    }
+                    count += 1;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Read the dimension of a spacing command like `\kern` or `\hspace` in its raw form.
+    ///
+    /// The dimension is either enclosed in braces, like `\hspace{1em}`, or it is not,
+    /// like `\kern1em`. Only in the latter case, `glue` determines whether glue like
+    /// `plus 1fil` is read as well.
+    fn read_dimension(&mut self, glue: bool) -> &'source str {
+        self.skip_whitespace();
+        if self.peek.1 == '*' {
+            // The star of `\hspace*` makes no difference for us.
+            self.read_char();
+            self.skip_whitespace();
+        }
+        let start = self.peek.0;
+        if self.peek.1 == '{' {
+            self.read_char();
+            // If the group is not closed, the rest of the input is returned, including
+            // the brace, so that it will be rejected as a dimension.
+            return self
+                .read_balanced('}')
+                .unwrap_or_else(|| self.input_string.get_unwrap(start..self.input_length));
+        }
+        if self.peek.1 == '#' {
+            // A macro parameter, which is replaced by the macro expander.
+            self.read_char();
+            self.read_char();
+            return self.input_string.get_unwrap(start..self.peek.0);
+        }
+        self.skip_length();
+        if glue {
+            for keyword in ["plus", "minus"] {
+                let mut lexer = self.clone();
+                lexer.skip_whitespace();
+                if lexer.input_string[lexer.peek.0..].starts_with(keyword) {
+                    for _ in 0..keyword.len() {
+                        lexer.read_char();
+                    }
+                    lexer.skip_length();
+                    *self = lexer;
+                }
+            }
+        }
+        self.input_string.get_unwrap(start..self.peek.0)
+    }
+
+    /// Skip a signed number followed by a unit, like `-1.5em` or `2 fil`.
+    fn skip_length(&mut self) {
+        while matches!(self.peek.1, '+' | '-') || self.peek.1.is_ascii_whitespace() {
+            self.read_char();
+        }
+        while matches!(self.peek.1, '.' | ',') || self.peek.1.is_ascii_digit() {
+            self.read_char();
+        }
+        self.skip_whitespace();
+        // The unit may be preceded by the keyword `true`, like in `1true in`.
+        if self.input_string[self.peek.0..].starts_with("true") {
+            for _ in 0.."true".len() {
+                self.read_char();
+            }
+            self.skip_whitespace();
+        }
+        // Units have two letters, except for the infinite units `fil`, `fill` and `filll`.
+        let mut unit_length = 0;
+        while self.peek.1.is_ascii_alphabetic() && unit_length < 2 {
+            self.read_char();
+            unit_length += 1;
+        }
+        if self.input_string[..self.peek.0].ends_with("fi") {
+            while self.peek.1 == 'l' {
+                self.read_char();
+            }
+        }
+    }
+
+    /// Read the character code of `\char`, or of `\unicode` and `\symbol` if `braced` is set,
+    /// and turn the character into a token.
+    ///
+    /// For `\char`, the code is a TeX number like `"2A01`, `'25001`, `` `A `` or `10753`.
+    fn read_char_code(&mut self, braced: bool) -> Token<'source> {
+        self.skip_whitespace();
+        let start = self.peek.0;
+        let code = if braced {
+            if self.peek.1 != '{' {
+                return Token::InvalidCharCode("");
+            }
+            self.read_char();
+            match self.read_balanced('}') {
+                Some(code) => code,
+                None => {
+                    return Token::InvalidCharCode(
+                        self.input_string.get_unwrap(start..self.input_length),
+                    )
+                }
+            }
+        } else {
+            match self.peek.1 {
+                '"' | '\'' => {
+                    let radix = if self.read_char().1 == '"' { 16 } else { 8 };
+                    while self.peek.1.is_digit(radix) {
+                        self.read_char();
+                    }
+                }
+                '`' => {
+                    self.read_char();
+                    if self.read_char().1 == '\\' {
+                        self.read_char();
+                    }
+                }
+                '#' => {
+                    // A macro parameter, which is replaced by the macro expander.
+                    self.read_char();
+                    self.read_char();
+                }
+                _ => {
+                    while self.peek.1.is_ascii_digit() {
+                        self.read_char();
+                    }
+                }
+            }
+            self.input_string.get_unwrap(start..self.peek.0)
+        };
+        match unicode::parse_char_code(code) {
+            Some(c) => unicode::classify(c),
+            None => Token::InvalidCharCode(code),
+        }
+    }
+
+    /// Read TeX's notation for characters like `^^^^2a01`, `^^e9` or `^^M`,
+    /// after the first `^` has already been read.
+    fn read_caret_notation(&mut self, start: usize) -> Option<Token<'source>> {
+        let input = self.input_string;
+        let rest = input.get(self.peek.0..)?;
+        // Six or four carets are followed by as many lowercase hex digits,
+        // and two carets by two of them.
+        for (carets, digits) in [(5, 6), (3, 4), (1, 2)] {
+            let (Some(prefix), Some(hex)) = (rest.get(..carets), rest.get(carets..carets + digits))
+            else {
+                continue;
+            };
+            if prefix.bytes().all(|b| b == b'^')
+                && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            {
+                for _ in 0..carets + digits {
+                    self.read_char();
+                }
+                let code = self.input_string.get_unwrap(start..self.peek.0);
+                return Some(
+                    u32::from_str_radix(hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .filter(|c| !c.is_control())
+                        .map_or(Token::InvalidCharCode(code), unicode::classify),
+                );
+            }
+        }
+        // Otherwise, `^^` shifts the code of the following ASCII character by 64.
+        let mut chars = rest.chars();
+        if chars.next() != Some('^') {
+            return None;
+        }
+        let c = chars.next().filter(|c| c.is_ascii())?;
+        self.read_char();
+        self.read_char();
+        let code = self.input_string.get_unwrap(start..self.peek.0);
+        let c = char::from(c as u8 ^ 0x40);
+        Some(if c.is_control() {
+            Token::InvalidCharCode(code)
+        } else {
+            unicode::classify(c)
+        })
+    }
+
     /// Generate the next token.
     pub(crate) fn next_token(&mut self, wants_digit: bool) -> TokLoc<'source> {
         if let Some(loc) = self.skip_whitespace() {
@@ -156,6 +523,7 @@ impl<'source> Lexer<'source> {
             '\u{0}' => Token::EOF,
             ' ' => Token::Letter('\u{A0}'),
             '!' => Token::Operator(ops::EXCLAMATION_MARK),
+            '$' => Token::Dollar,
             '&' => Token::Ampersand,
             '\'' => Token::Prime,
             '(' => Token::Paren(ops::LEFT_PARENTHESIS, None, Stretchy::Always),
@@ -172,7 +540,7 @@ impl<'source> Lexer<'source> {
             '>' => Token::OpGreaterThan,
             '[' => Token::SquareBracketOpen,
             ']' => Token::SquareBracketClose,
-            '^' => Token::Circumflex,
+            '^' => self.read_caret_notation(loc).unwrap_or(Token::Circumflex),
             '_' => Token::Underscore,
             '{' => Token::GroupBegin,
             '|' => Token::Paren(
@@ -182,14 +550,23 @@ impl<'source> Lexer<'source> {
             ),
             '}' => Token::GroupEnd,
             '~' => Token::NonBreakingSpace,
-            '\\' => {
-                let cmd = get_command(self.read_command());
-                if self.text_mode {
-                    // After a command, all whitespace is skipped, even in text mode.
-                    self.skip_whitespace();
+            '\\' => match get_command(self.read_command()) {
+                // Dimensions are not made up of tokens, so they are read right away.
+                Token::Kern => Token::Dimension(self.read_dimension(false)),
+                Token::Skip => Token::Dimension(self.read_dimension(true)),
+                Token::MathKern => Token::MathDimension(self.read_dimension(false)),
+                Token::MathSkip => Token::MathDimension(self.read_dimension(true)),
+                // So are character codes, which turn into the token of the character.
+                Token::Char => self.read_char_code(false),
+                Token::Unicode => self.read_char_code(true),
+                cmd => {
+                    if self.text_mode {
+                        // After a command, all whitespace is skipped, even in text mode.
+                        self.skip_whitespace();
+                    }
+                    cmd
                 }
-                cmd
-            }
+            },
             c => {
                 if c.is_ascii_digit() {
                     self.read_number(loc)
@@ -244,6 +621,7 @@ mod tests {
             ("simple_expression", r"x+y", false),
             ("space_and_number", r"\ 1", false),
             ("space_in_text", r"  x   y z", true),
+            ("kern", r"\kern-1.5em x\hskip 2pt plus 1fill y", false),
         ];
 
         for (name, problem, text_mode) in problems.into_iter() {
@@ -252,7 +630,7 @@ mod tests {
             // Call `lexer.next_token(false)` until we get `Token::EOF`.
             let mut tokens = String::new();
             if text_mode {
-                write!(tokens, "(text mode)\n").unwrap();
+                writeln!(tokens, "(text mode)").unwrap();
             }
             loop {
                 let tokloc = lexer.next_token(false);
@@ -260,7 +638,7 @@ mod tests {
                     break;
                 }
                 let TokLoc(loc, tok) = tokloc;
-                write!(tokens, "{}: {:?}\n", loc, tok).unwrap();
+                writeln!(tokens, "{}: {:?}", loc, tok).unwrap();
             }
             assert_snapshot!(name, &tokens, problem);
         }
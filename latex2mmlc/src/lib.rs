@@ -15,18 +15,48 @@
 //! - Integrals, e.g., `\int_0^\infty`, `\iint`, `\oint`, ...
 //! - Big operators, e.g., `\sum`, `\prod`, `\bigcup_{i = 0}^\infty`, ...
 //! - Limits and overset/underset, e.g., `\lim`, `\overset{}{}`, `\overbrace{}{}`, ...
+//! - Stacked relations: `\stackrel{}{}`, `\stackbin{}{}`, `\overunderset{}{}{}`, `\underoverset{}{}{}`.
 //! - Font styles, e.g. `\mathrm`, `\mathbf`, `\bm`, `\mathit`, `\mathsf`, `\mathscr`, `\mathbb`, `\mathfrak`, `\texttt`.
+//! - Old font declarations like `{\rm d}x` and `{\bf v}`, which last until the end of the group.
+//! - Style switches like `\displaystyle`, which also last until the end of the group.
 //!   - MathML lacks calligraphic mathvariant: https://github.com/mathml-refresh/mathml/issues/61
-//! - White spaces, e.g., `\!`, `\,`, `\:`, `\;`, `\ `, `\quad`, `\qquad`.
+//! - White spaces, e.g., `\!`, `\,`, `\:`, `\;`, `\ `, `\quad`, `\qquad`, `\enspace`, `\negthinspace`.
+//! - Explicit spacing with dimensions, e.g. `\kern0.5em`, `\mkern-2mu`, `\hspace{3pt}`, `\hskip`, `\mskip`, `\mspace`.
 //! - Matrix, e.g. `\begin{matrix}`, `\begin{pmatrix}`, `\begin{bmatrix}`, `\begin{vmatrix}`.
-//! - Multi-line equation `\begin{align}` (experimental).
+//! - Multi-line equation `\begin{align}` (experimental), `\begin{gather}`, `\begin{multline}`, `\begin{split}`,
+//!   `\begin{alignat}{n}`, `\begin{flalign}`, `\begin{equation}`, `\begin{gathered}`, `\begin{alignedat}{n}`.
+//! - Equation tags `\tag{..}`, `\tag*{..}`, `\notag` and `\label{..}`, and automatic equation numbers
+//!   with [`Options::equation_counter`].
+//! - Arrays with a column specification, e.g. `\begin{array}{r|c@{}l}`, with `\hline`, `\hdashline` and `\cline{i-j}`.
+//! - Multi-line limits `\substack{...}` and `\begin{subarray}{l}`.
+//! - Operators with limits: `\operatorname*`, `\operatornamewithlimits`, and `\limits`, `\nolimits`
+//!   and `\displaylimits` after any operator.
+//! - Atom classes: `\mathord`, `\mathop`, `\mathbin`, `\mathrel`, `\mathopen`, `\mathclose`, `\mathpunct`, `\mathinner`.
+//! - Rules and struts: `\rule[raise]{width}{height}`, `\strut`.
+//! - Phantoms and overlaps: `\phantom`, `\hphantom`, `\vphantom`, `\smash[t]`, `\mathllap`, `\mathrlap`, `\mathclap`.
+//! - Pre- and post-scripts: `{}_a^b X`, `\prescript{a}{b}{X}` and `\sideset{_a^b}{_c^d}\sum`.
+//! - Modular arithmetic: `\pmod{n}`, `\bmod`, `\pod{n}` and `\mod{n}`.
 //! - Feynman slash notation: `\slashed{\partial}`.
+//! - Chemistry with a subset of mhchem: `\ce{2H2 + O2 -> 2H2O}` and units with `\pu{kJ mol-1}`.
+//! - Numbers and units like in siunitx: `\num`, `\unit`, `\qty`, `\ang`, `\si`, `\SI` and `\sisetup`.
+//! - The physics package: `\bra`, `\ket`, `\braket`, `\abs`, `\norm`, `\qty(...)`, `\dv`, `\pdv`,
+//!   `\eval`, `\order` and `\vb`.
+//! - Links: `\href{url}{math}` and `\url{url}`, if they are allowed by the [`LinkPolicy`].
+//! - HTML attributes: `\htmlClass`, `\htmlId`, `\htmlStyle` and `\htmlData`, if the
+//!   `html_attributes` option is set.
+//! - Raw characters: `\unicode{x2A01}`, `\char"2A01`, `\symbol{"2A01}` and `^^^^2a01`, which
+//!   become operators, fences or identifiers according to their Unicode math class.
+//! - User-defined macros: `\newcommand`, `\renewcommand` and `\def`, with up to nine arguments,
+//!   and operators with `\DeclareMathOperator`.
+//! - Colors: `\color`, `\textcolor`, `\colorbox`, `\fcolorbox` and `\definecolor`.
+//! - Framed boxes: `\boxed`, `\fbox`, `\framebox` and `\bbox`.
+//! - Cancellations: `\cancel`, `\bcancel`, `\xcancel`, `\cancelto` and `\sout`.
+//! - Extensible arrows, e.g. `\xrightarrow[below]{above}`, `\xRightarrow`, `\xmapsto`, `\xhookrightarrow`, ...
 //!
 //! ## Unsupported LaTeX commands
 //!
 //! - New line `\\`, except for ones in a matrix or align environment.
 //! - Alignment `&`, except for ones in a matrix or align environment.
-//! - Complicated sub/superscripts (`<mmultiscripts>`).
 //!
 //!
 //! # Usage
@@ -51,13 +81,20 @@ use arena::Arena;
 pub mod arena;
 pub mod ast;
 pub mod attribute;
+pub(crate) mod chem;
+pub(crate) mod color;
 pub(crate) mod commands;
+pub(crate) mod dimension;
 mod error;
 pub(crate) mod lexer;
+mod macros;
 pub(crate) mod ops;
 pub(crate) mod parse;
 pub mod token;
+pub(crate) mod unicode;
+pub(crate) mod units;
 pub use error::{LatexErrKind, LatexError};
+pub use macros::Macros;
 
 /// display
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -66,10 +103,100 @@ pub enum Display {
     Inline,
 }
 
+/// Additional settings for the conversion.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options<'a> {
+    /// Macros which are defined before the formula is read, e.g. from a document preamble.
+    pub macros: Option<&'a Macros>,
+    /// The value of the equation counter before the formula is read.
+    ///
+    /// If this is set, the rows of numbered environments like `equation` and `align`
+    /// are numbered automatically, starting with the next value.
+    pub equation_counter: Option<u32>,
+    /// Put the equation tags on the left, like the `leqno` option of LaTeX.
+    pub left_tags: bool,
+    /// The links which are allowed in `\href` and `\url`. By default, there are none.
+    pub links: LinkPolicy<'a>,
+    /// Allow `\htmlClass`, `\htmlId`, `\htmlStyle`, `\htmlData` and `\bbox[style:...]`,
+    /// which put attributes on the output. They are not allowed by default, because styles can change anything
+    /// on the page.
+    pub html_attributes: bool,
+    /// Options for units and quantities like `per-mode=fraction, group-digits=false`,
+    /// which apply as if they were given to `\sisetup` at the start of the formula.
+    pub unit_options: &'a str,
+}
+
+/// Which links are allowed in `\href` and `\url`.
+///
+/// Links can lead anywhere, including to `javascript:` URLs, so they have to be
+/// allowed explicitly if the input is not trusted.
+///
+/// ```rust
+/// use latex2mmlc::{latex_to_mathml_with_options, Display, LinkPolicy, Options};
+///
+/// let options = Options {
+///     links: LinkPolicy {
+///         schemes: &["https"],
+///         fragments: true,
+///     },
+///     ..Default::default()
+/// };
+/// let latex = r"\href{#def-entropy}{H(X)}";
+/// assert!(latex_to_mathml_with_options(latex, Display::Inline, false, &options).is_ok());
+/// let latex = r"\href{javascript:alert(1)}{x}";
+/// assert!(latex_to_mathml_with_options(latex, Display::Inline, false, &options).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkPolicy<'a> {
+    /// The allowed URL schemes without the colon, like `"https"`. They are compared
+    /// without regard to case.
+    pub schemes: &'a [&'a str],
+    /// Whether links to fragments of the same document, like `#def-entropy`, are allowed.
+    pub fragments: bool,
+}
+
+impl LinkPolicy<'_> {
+    /// Whether the policy allows a link to `url`.
+    ///
+    /// Relative links like `page.html` are never allowed, because they don't have a scheme.
+    pub fn allows(&self, url: &str) -> bool {
+        // Browsers ignore whitespace and control characters in some places, which
+        // could be used to hide the scheme.
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return false;
+        }
+        if url.starts_with('#') {
+            return self.fragments;
+        }
+        let Some((scheme, _)) = url.split_once(':') else {
+            return false;
+        };
+        let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        is_scheme
+            && self
+                .schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+}
+
+/// An equation number which was assigned automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EquationNumber {
+    pub number: u32,
+    /// The key of the `\label` in the same row, if there is one.
+    pub label: Option<String>,
+}
+
 fn get_nodes<'arena, 'source>(
     latex: &'source str,
+    display: Display,
     arena: &'arena Arena,
-) -> Result<ast::Node<'arena>, error::LatexError<'source>>
+    options: &Options<'source>,
+) -> Result<(ast::Node<'arena>, Vec<EquationNumber>), error::LatexError<'source>>
 where
     'source: 'arena, // 'source outlives 'arena
 {
@@ -77,10 +204,15 @@ where
     // the string buffer.
     // let buffer = Buffer::new(latex.len());
 
-    let l = lexer::Lexer::new(latex);
+    let l = macros::MacroExpander::new(lexer::Lexer::new(latex), options.macros);
     let mut p = parse::Parser::new(l, arena);
+    p.set_display(matches!(display, Display::Block));
+    p.set_equation_numbering(options.equation_counter, options.left_tags);
+    p.set_link_policy(options.links);
+    p.set_html_attributes(options.html_attributes);
+    p.set_unit_options(options.unit_options)?;
     let nodes = p.parse()?;
-    Ok(nodes)
+    Ok((nodes, p.equation_numbers))
 }
 
 /// Convert LaTeX text to MathML.
@@ -104,8 +236,18 @@ pub fn latex_to_mathml(
     display: Display,
     pretty: bool,
 ) -> Result<String, error::LatexError<'_>> {
+    latex_to_mathml_with_options(latex, display, pretty, &Options::default())
+}
+
+/// Same as `latex_to_mathml`, but with additional settings.
+pub fn latex_to_mathml_with_options<'source>(
+    latex: &'source str,
+    display: Display,
+    pretty: bool,
+    options: &Options<'source>,
+) -> Result<String, error::LatexError<'source>> {
     let mut output = String::new();
-    append_mathml(&mut output, latex, display, pretty)?;
+    append_mathml_with_options(&mut output, latex, display, pretty, options)?;
     Ok(output)
 }
 
@@ -116,8 +258,47 @@ pub fn append_mathml<'source>(
     display: Display,
     pretty: bool,
 ) -> Result<(), error::LatexError<'source>> {
+    append_mathml_with_options(output, latex, display, pretty, &Options::default())
+}
+
+/// Same as `append_mathml`, but with additional settings.
+pub fn append_mathml_with_options<'source>(
+    output: &mut String,
+    latex: &'source str,
+    display: Display,
+    pretty: bool,
+    options: &Options<'source>,
+) -> Result<(), error::LatexError<'source>> {
+    append_mathml_numbered(output, latex, display, pretty, options)?;
+    Ok(())
+}
+
+/// Same as `append_mathml_with_options`, but returns the equation numbers which
+/// were assigned automatically.
+///
+/// ```rust
+/// use latex2mmlc::{append_mathml_numbered, Display, Options};
+///
+/// let options = Options {
+///     equation_counter: Some(3),
+///     ..Default::default()
+/// };
+/// let mut mathml = String::new();
+/// let latex = r"\begin{align} a &= b \label{first} \\ c &= d \notag \\ e &= f \end{align}";
+/// let numbers = append_mathml_numbered(&mut mathml, latex, Display::Block, false, &options).unwrap();
+/// assert_eq!(numbers[0].number, 4);
+/// assert_eq!(numbers[0].label.as_deref(), Some("first"));
+/// assert_eq!(numbers[1].number, 5);
+/// ```
+pub fn append_mathml_numbered<'source>(
+    output: &mut String,
+    latex: &'source str,
+    display: Display,
+    pretty: bool,
+    options: &Options<'source>,
+) -> Result<Vec<EquationNumber>, error::LatexError<'source>> {
     let arena = Arena::new();
-    let nodes = get_nodes(latex, &arena)?;
+    let (nodes, equation_numbers) = get_nodes(latex, display, &arena, options)?;
 
     match display {
         Display::Block => output.push_str("<math display=\"block\">"),
@@ -129,20 +310,22 @@ pub fn append_mathml<'source>(
         output.push('\n');
     }
     output.push_str("</math>");
-    Ok(())
+    Ok(equation_numbers)
 }
 
 #[cfg(test)]
 mod tests {
     use insta::assert_snapshot;
 
-    use crate::{error, latex_to_mathml, LatexError};
+    use crate::{
+        error, latex_to_mathml, latex_to_mathml_with_options, LatexError, LinkPolicy, Macros,
+    };
 
-    use super::{get_nodes, Arena};
+    use super::{get_nodes, Arena, Display, Options};
 
-    fn convert_content(latex: &str) -> Result<String, error::LatexError> {
+    fn convert_content(latex: &str) -> Result<String, error::LatexError<'_>> {
         let arena = Arena::new();
-        let nodes = get_nodes(latex, &arena)?;
+        let (nodes, _) = get_nodes(latex, Display::Inline, &arena, &Options::default())?;
         Ok(nodes.render())
     }
 
@@ -226,6 +409,21 @@ mod tests {
             ("sum_with_special_symbol", r"\sum_{i = 0}^∞ i"),
             ("sum_with_limit", r"\sum\limits_{i=1}^N"),
             ("sum_pointless_limits", r"\sum\limits n"),
+            ("sum_nolimits", r"\sum\nolimits_{i=1}^N"),
+            ("integral_displaylimits", r"\int\displaylimits_0^1 dx"),
+            ("lim_under", r"\lim_{x \to 0} f(x)"),
+            ("function_limits", r"\max\limits_{x} f(x)"),
+            ("operatorname_star", r"\operatorname*{arg\,max}_{\theta} L"),
+            ("operatorname_limits", r"\operatorname{ess\,sup}\limits_{x}"),
+            (
+                "operatorname_star_limits",
+                r"\operatorname*{argmax}\limits_a",
+            ),
+            ("function_nolimits", r"\lim\nolimits_{x \to 0}"),
+            (
+                "operatornamewithlimits",
+                r"\operatornamewithlimits{argmin}_{x} f(x)",
+            ),
             ("product", r"\prod_n n"),
             ("underscore", r"x\ y"),
             ("stretchy_brace", r"\left\{ x  ( x + 2 ) \right\}"),
@@ -235,6 +433,38 @@ mod tests {
                 "align",
                 r#"\begin{align} f ( x ) &= x^2 + 2 x + 1 \\ &= ( x + 1 )^2\end{align}"#,
             ),
+            ("gather", r"\begin{gather} a = b \\ c + d = e \end{gather}"),
+            (
+                "multline",
+                r"\begin{multline} a + b + c \\ + d + e \\ + f \end{multline}",
+            ),
+            (
+                "split",
+                r"\begin{equation} \begin{split} a &= b \\ &= c \end{split} \end{equation}",
+            ),
+            (
+                "alignat",
+                r"\begin{alignat}{2} x &= 1 & y &= 2 \\ x' &= 3 & y' &= 4 \end{alignat}",
+            ),
+            (
+                "flalign",
+                r"\begin{flalign*} a &= b & c &= d \end{flalign*}",
+            ),
+            ("equation", r"\begin{equation*} E = mc^2 \end{equation*}"),
+            (
+                "alignedat_centered",
+                r"x = \begin{alignedat}[c]{1} a &= b \\ c &= d \end{alignedat}",
+            ),
+            ("tag", r"\begin{equation} E = mc^2 \tag{1.1} \end{equation}"),
+            ("tag_star", r"\begin{gather*} a = b \tag*{A1} \end{gather*}"),
+            (
+                "tag_rows",
+                r"\begin{align*} a &= b \tag{A} \\ c &= d \end{align*}",
+            ),
+            ("tag_top_level", r"x + y = z \tag{3}"),
+            ("tag_escaped", r"x \tag{\& a<b}"),
+            ("tag_math", r"x \tag{$a<b$}"),
+            ("tag_star_math", r"x \tag*{step $n+1$}"),
             (
                 "text_transforms",
                 r#"{fi}\ \mathit{fi}\ \mathrm{fi}\ \texttt{fi}"#,
@@ -245,6 +475,42 @@ mod tests {
                 r"f(x):=\begin{cases}0 &\text{if } x\geq 0\\1 &\text{otherwise.}\end{cases}",
             ),
             ("mathstrut", r"\mathstrut"),
+            ("mathrel_accent", r"a \mathrel{\widehat=} b"),
+            ("mathbin_letter", r"a \mathbin{\#} b"),
+            ("mathord_operator", r"\mathord{+} a \mathord{-}"),
+            ("mathrel_group", r"a \mathrel{x y} b"),
+            ("mathpunct", r"f(a \mathpunct{.} b)"),
+            ("mathopen_mathclose", r"\mathopen{[} a, b \mathclose{[}"),
+            ("mathinner", r"a \mathinner{\ldots} b"),
+            ("mathop_limits", r"\mathop{\mathrm{argmax}}_{x \in X} f(x)"),
+            ("mathop_symbol", r"\mathop{\star}\limits_{i=1}^n a_i"),
+            ("mathop_movable_limits", r"\mathop{x}_a^b"),
+            ("mathop_nolimits", r"\mathop{x}\nolimits_a^b"),
+            ("rule", r"x = \rule{2cm}{0.4pt}"),
+            ("rule_raised", r"a\rule[-1mm]{1em}{1ex}b"),
+            ("rule_zero_width", r"\rule{0pt}{3ex} x"),
+            ("strut", r"\frac{\strut a}{b}"),
+            ("kern", r"a\kern0.5em b \kern -3pt c"),
+            ("mkern", r"a \mkern-2mu b \mskip 18mu c"),
+            ("kern_true", r"a \kern 12true pt b \hskip 1truein c"),
+            ("hspace", r"a\hspace{3pt}b\hspace*{1cm}c\mspace{9mu}d"),
+            ("hskip_glue", r"a \hskip 1em plus 2pt minus 1fil b"),
+            (
+                "kern_units",
+                r"\kern10bp \kern1dd \kern1pc \kern2mm \kern.5in \kern1ex",
+            ),
+            (
+                "named_spaces",
+                r"a\enspace b\thinspace c\negthinspace d\negmedspace e\negthickspace f",
+            ),
+            ("phantom", r"a + \phantom{b} + c"),
+            (
+                "hphantom_vphantom",
+                r"\hphantom{\frac{1}{2}} \vphantom{\int} x",
+            ),
+            ("smash", r"\sqrt{\smash[b]{y}} \smash{\frac{a}{b}}"),
+            ("mathclap", r"\sum_{\mathclap{1 \le i \le n}} x_i"),
+            ("mathllap_mathrlap", r"\mathllap{a} = \mathrlap{b}"),
             ("greater_than", r"x > y"),
             ("text_transform_sup", r"\mathbb{N} \cup \mathbb{N}^+"),
             ("overbrace", r"\overbrace{a+b+c}^{d}"),
@@ -255,6 +521,44 @@ mod tests {
                 r"\sum_{\genfrac{}{}{0pt}{}{\scriptstyle 0 \le i \le m}{\scriptstyle 0 < j < n}} P(i, j)",
             ),
             ("genfrac", r"\genfrac(]{0pt}{2}{a+b}{c+d}"),
+            (
+                "substack",
+                r"\sum_{\substack{0 \le i \le m \\ 0 < j < n}} P(i, j)",
+            ),
+            (
+                "subarray",
+                r"\sum_{\begin{subarray}{l} i \in \Lambda \\ 0 < j < n \end{subarray}} P(i, j)",
+            ),
+            (
+                "array",
+                r"\begin{array}{r|cl} 1 & 2 & 3 \\ \hline x & y & z \end{array}",
+            ),
+            (
+                "array_rules",
+                r"\begin{array}{|c|c:c|} a & b & S \\ \hline 0 & 0 & 1 \\ \hdashline 1 & 1 & 0 \\ \hline \end{array}",
+            ),
+            (
+                "array_double_rules",
+                r"\begin{array}{c||c} \hline\hline a & b \\ c & d \end{array}",
+            ),
+            (
+                "array_cline",
+                r"\begin{array}{ccc} a & b & c \\ \cline{2-3} d & e & f \end{array}",
+            ),
+            (
+                "array_material",
+                r"\begin{array}{r@{.}l} 3 & 14 \\ 2 & 72 \end{array}",
+            ),
+            (
+                "array_empty_material",
+                r"\begin{array}{@{}c!{=}c@{}} a & b \end{array}",
+            ),
+            (
+                "array_p_column",
+                r"\begin{array}{p{2cm}c} a & b \end{array}",
+            ),
+            ("darray", r"\begin{darray}{cc} \frac{1}{2} & x \end{darray}"),
+            ("substack_frac", r"\prod_{\substack{\frac{1}{2} \\ x}} y"),
             ("not_subset", r"\not\subset"),
             ("not_less_than", r"\not\lt"),
             ("not_less_than_symbol", r"\not< x"),
@@ -290,13 +594,137 @@ mod tests {
             ("double_prime_and_sub_super", r"f''_3^2"),
             ("sum_prime", r"\sum'"),
             ("int_prime", r"\int'"),
+            ("pmod", r"a \equiv b \pmod{n}"),
+            ("bmod", r"\gcd(a, b) = \gcd(b, a \bmod b)"),
+            ("pod", r"x = y \pod{m}"),
+            ("mod", r"x \equiv y \mod{m}"),
+            ("prescripts_on_empty_group", r"{}^{14}_{6}\mathrm{C}^{4+}"),
+            ("scripts_on_empty_group", r"x = {}_a^b"),
+            ("tensor_indices", r"R{}^a{}_b"),
+            ("prescript", r"\prescript{14}{2}{\mathrm{C}}"),
+            ("sideset", r"\sideset{_a^b}{'_c}\sum_{i=1}^n"),
+            ("sideset_multiple", r"\sideset{_1^2_3^4}{}X"),
             ("vec_prime", r"\vec{x}'"),
             ("overset_with_prime", r"\overset{!}{=}'"),
             ("overset_prime", r"\overset{'}{=}"),
+            ("stackrel", r"a \stackrel{\text{def}}{=} b"),
+            ("stackrel_letter", r"a \stackrel{!}{x} b"),
+            ("stackrel_below", r"a \stackrel[n]{*}{\to} b"),
+            ("stackbin", r"a \stackbin{k}{+} b"),
+            (
+                "overunderset",
+                r"a \overunderset{f}{\sim}{\longrightarrow} b",
+            ),
+            ("underoverset", r"\underoverset{0}{\infty}{=}"),
+            ("ce_reaction", r"\ce{2H2 + O2 -> 2H2O}"),
+            ("ce_isotope", r"\ce{^{14}_{6}C}"),
+            ("ce_charge", r"\ce{SO4^2-}"),
+            ("ce_equilibrium_labels", r"\ce{A <=>[k_1][k_{-1}] B}"),
+            ("ce_arrow_text_label", r"\ce{A ->[{text above}][$x$] B}"),
+            ("ce_unbalanced_equilibrium", r"\ce{A <=>> B <<=>[k] C}"),
+            ("ce_bonds", r"\ce{CH3-CH=CH2}"),
+            ("ce_addition", r"\ce{CuSO4*5H2O}"),
+            ("ce_oxidation_state", r"\ce{Fe^{II}}"),
+            ("ce_state", r"\ce{NaCl(aq)}"),
+            ("ce_math", r"\ce{$x$ A + $\frac{1}{2}$ B}"),
+            (
+                "ce_math_with_color",
+                r"\definecolor{my}{rgb}{1,0,0}\ce{$\color{my}x$}",
+            ),
+            ("ce_math_with_macro", r"\newcommand\n{n}\ce{$\n$ H2O}"),
+            ("pu", r"\pu{1.2e3 kJ mol-1}"),
+            ("num", r"\num{12345.6789}"),
+            ("num_uncertainty", r"\num{-1.23(4)e-5}"),
+            ("num_separate_uncertainty", r"\num{1.2 +- 0.1 e3}"),
+            (
+                "num_options",
+                r"\num[output-decimal-marker={,}, exponent-product=\cdot]{6.022e23}",
+            ),
+            ("unit", r"\unit{\kilo\metre\per\hour}"),
+            ("unit_literal", r"\si{kg.m/s^2}"),
+            (
+                "unit_fraction",
+                r"\unit[per-mode=fraction]{\joule\per\mole\per\kelvin}",
+            ),
+            (
+                "unit_symbol",
+                r"\unit[per-mode=symbol]{\joule\per\mole\per\kelvin}",
+            ),
+            ("qty", r"\qty{9.81}{\metre\per\second\squared}"),
+            ("si_quantity", r"\SI{3e8}{\m\per\s}"),
+            ("qty_degree", r"\qty{90}{\degree}"),
+            ("ang", r"\ang{12;34;56.7}"),
+            (
+                "sisetup",
+                r"\sisetup{per-mode=symbol} \qty{1}{\metre\per\second} \qty{2}{\per\second}",
+            ),
+            (
+                "sisetup_as_argument",
+                r"\frac{\sisetup{per-mode=fraction}}{2}",
+            ),
+            ("bra_ket", r"\bra{\phi} \ket{\psi}"),
+            ("braket", r"\braket{\phi|\psi}"),
+            ("braket_two_arguments", r"\braket{\phi}{\frac{1}{2}}"),
+            ("ket_starred", r"\ket*{\psi}"),
+            ("abs_norm", r"\abs{x} + \norm{\frac{v}{2}}"),
+            ("dv", r"\dv{f}{x}"),
+            ("dv_order", r"\dv[2]{f}{x}"),
+            ("dv_operator", r"\dv{t}"),
+            ("dv_slashed", r"\dv*{f}{x}"),
+            ("pdv", r"\pdv{f}{x}"),
+            ("pdv_mixed", r"\pdv{f}{x}{y}"),
+            ("pdv_order", r"\pdv[n]{f}{x}"),
+            ("dv_order_with_macro", r"\newcommand\n{n}\dv[\n]{f}{x}"),
+            (
+                "pdv_order_with_color",
+                r"\definecolor{my}{rgb}{0,0,1}\pdv[\color{my}2]{f}{x}",
+            ),
+            ("physics_qty", r"\qty(\frac{a}{b})"),
+            ("physics_qty_brackets", r"\qty[x] \qty|y| \qty\{z\}"),
+            ("physics_qty_starred", r"\qty*(x)"),
+            ("eval", r"\eval{x^2}_0^1"),
+            ("eval_paren", r"\eval(x^2|_0^1"),
+            ("order", r"\order{x^2}"),
+            ("vb", r"\vb{a} \vb*{\alpha}"),
+            ("unicode_big_op", r"\unicode{x2A01}_{i} A_i"),
+            ("unicode_integral", r"\unicode{8749}_0^1 f"),
+            ("unicode_letter", r"\unicode{x1D49C} + \unicode{x3B1}"),
+            (
+                "unicode_fence",
+                r"\left\unicode{x27E6} x \right\unicode{x27E7}",
+            ),
+            ("unicode_less_than", r"a \unicode{x3C} b"),
+            ("unicode_in_text", r"\text{caf\unicode{xE9}}"),
+            ("char_hex", r#"a \char"2A2F b"#),
+            ("char_octal_and_decimal", r"a \char'53 b \char43 c"),
+            ("char_backtick", r"\char`\{ x \char`\}"),
+            ("symbol", r#"a \symbol{"2225} b"#),
+            ("caret_notation", r"x ^^^^2264 y ^^^^^^01d400 ^^e9 ^^k"),
             ("int_limit_prime", r"\int\limits'"),
             ("prime_command", r"f^\prime"),
             ("prime_command_braces", r"f^{\prime}"),
             ("transform_group", r"\mathit{a{bc}d}"),
+            ("displaystyle_top_level", r"\displaystyle \sum_{i} x_i"),
+            ("textstyle_in_group", r"a {\textstyle \frac12} b"),
+            (
+                "scriptstyle_in_cell",
+                r"\begin{matrix}\scriptstyle a & b\end{matrix}",
+            ),
+            (
+                "displaystyle_in_left_right",
+                r"\left(\displaystyle \frac12 \right) x",
+            ),
+            ("displaystyle_pmod", r"\displaystyle a \pmod{n}"),
+            ("style_switch_twice", r"\scriptstyle a \scriptscriptstyle b"),
+            ("font_switch_rm", r"{\rm d}x"),
+            ("font_switch_bf", r"a + {\bf x y} + z"),
+            ("font_switch_to_end", r"x \cal L + M"),
+            (
+                "font_switch_in_table",
+                r"\begin{matrix}\bf a & b\end{matrix}",
+            ),
+            ("font_switch_nested", r"{\bf a {\it b} c}"),
+            ("font_switch_boldmath", r"{\boldmath \alpha + \mathrm{d}x}"),
             ("nabla_in_mathbf", r"\mathbf{\nabla} + \nabla"),
             ("vertical_line", r"P(x|y)"),
             ("mid", r"P(x\mid y)"),
@@ -308,11 +736,97 @@ mod tests {
                 r"\left(\frac12\middle\uparrow\frac12\right)",
             ),
             ("middle_bracket", r"\left(\frac12\middle]\frac12\right)"),
+            ("newcommand", r"\newcommand{\R}{\mathbb{R}} x \in \R"),
+            (
+                "newcommand_with_args",
+                r"\newcommand{\nrm}[1]{\left\| #1 \right\|} \nrm{v}",
+            ),
+            (
+                "newcommand_default_arg",
+                r"\newcommand\pow[2][2]{#2^{#1}} \pow{x} + \pow[3]y",
+            ),
+            (
+                "renewcommand",
+                r"\renewcommand{\sin}{\operatorname{sn}} \sin x",
+            ),
+            ("def_with_args", r"\def\pair#1#2{(#1, #2)} \pair ab"),
+            ("def_in_group", r"\def\a{x}{\def\a{y}\a}\a"),
+            (
+                "def_in_environment",
+                r"\def\b{x}\begin{matrix}\def\b{y}\b\end{matrix}\b",
+            ),
+            (
+                "nested_macros",
+                r"\newcommand{\sq}[1]{#1^2}\newcommand{\nrm}[1]{\left\| #1 \right\|}\sq{\nrm{x}}",
+            ),
+            (
+                "macro_argument_after_body",
+                r"\newcommand{\twice}[1]{#1#1}\newcommand{\b}{\twice}\b{x}",
+            ),
+            (
+                "declare_math_operator",
+                r"\DeclareMathOperator{\tr}{tr} \DeclareMathOperator*{\esssup}{ess\,sup} \tr_1 A + \esssup_x f",
+            ),
+            (
+                "macro_in_text",
+                r"\newcommand{\hi}{hello}\text{\hi{} there}",
+            ),
+            ("macro_with_ce", r"\newcommand\cee[1]{\ce{#1}}\cee{H2O}"),
+            ("macro_with_color", r"\newcommand\c[1]{\color{#1}x}\c{red}"),
+            (
+                "macro_with_nested_color",
+                r"\newcommand\c[1]{\color{#1}x}\newcommand\d[1]{\c{#1} y}\d{blue}",
+            ),
+            (
+                "macro_with_hspace",
+                r"\newcommand\h[1]{a\hspace{#1}b}\h{1em}",
+            ),
+            ("macro_with_kern", r"\def\k#1{a\kern#1 b}\k{2pt}"),
+            (
+                "macro_with_unicode",
+                r"\newcommand\uc[1]{\unicode{#1}}\uc{x2A01}",
+            ),
+            ("color", r"\color{red} x + y"),
+            ("color_in_group", r"{\color{blue} a} b"),
+            (
+                "color_in_table",
+                r"\begin{matrix} \color{red} a & b \\ c & d \end{matrix}",
+            ),
+            ("textcolor", r"\textcolor{red!50}{x+y}^2"),
+            ("textcolor_with_model", r"\textcolor[HTML]{1A2B3C}x"),
+            ("colorbox", r"\colorbox{yellow}{hi there}"),
+            ("fcolorbox", r"\fcolorbox{red}{yellow}{hi}"),
+            (
+                "definecolor",
+                r"\definecolor{myblue}{RGB}{0,0,200}\color{myblue!50!red} x",
+            ),
+            (
+                "definecolor_as_argument",
+                r"\frac{\definecolor{c}{rgb}{1,0,0}}{2}",
+            ),
+            ("boxed", r"\boxed{\frac{a}{b}}"),
+            ("fbox", r"\fbox{final answer}"),
+            ("framebox", r"\framebox[2cm][l]{x y}"),
+            ("framebox_centered", r"\framebox[3em]{x}"),
+            ("framebox_right", r"\framebox[2cm][r]{x}"),
+            ("framebox_without_width", r"\framebox{x}"),
+            ("bbox", r"\bbox[yellow, 5px, border: 2px solid red]{x^2}"),
+            ("cancel", r"\cancel{x+1}"),
+            ("bcancel", r"\bcancel x"),
+            ("xcancel", r"\xcancel{\frac{a}{b}}"),
+            ("cancelto", r"\cancelto{0}{x^2}"),
+            ("sout", r"\sout{abc}"),
+            ("xrightarrow", r"A \xrightarrow{k_1} B"),
+            (
+                "xrightarrow_with_below",
+                r"A \xrightarrow[\text{heat}]{k_1} B",
+            ),
+            ("xleftrightharpoons", r"A \xleftrightharpoons[]{} B"),
         ];
 
         for (name, problem) in problems.into_iter() {
             let mathml = latex_to_mathml(problem, crate::Display::Inline, true)
-                .expect(format!("failed to convert `{}`", problem).as_str());
+                .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
             assert_snapshot!(name, &mathml, problem);
         }
     }
@@ -357,7 +871,99 @@ mod tests {
             ("sub_super_then_prime", "f_5^2'"),
             ("sup_sup", "x^2^3 y"),
             ("sub_sub", "x_2_3 y"),
+            ("sideset_without_scripts", r"\sideset{a}{}\sum"),
+            ("ce_unclosed_label", r"\ce{A ->[x B}"),
+            ("ce_unclosed", r"\ce{H2O"),
+            ("tag_unclosed_math", r"x \tag{$a}"),
+            ("tag_in_argument", r"\frac{\tag{1}}{2}"),
+            ("label_in_script", r"x^\label{a}"),
+            ("tag_twice", r"x \tag{1} \tag{2}"),
+            ("tag_in_matrix", r"\begin{pmatrix} a \tag{1} \end{pmatrix}"),
+            (
+                "label_duplicate",
+                r"\begin{align} a \label{x} \\ b \label{x} \end{align}",
+            ),
+            ("num_invalid", r"\num{1.2.3}"),
+            ("unit_unknown", r"\unit{\metre\furlong}"),
+            (
+                "unit_option_invalid",
+                r"\qty[per-mode=reciprocal]{1}{\metre}",
+            ),
+            ("physics_qty_unclosed", r"\qty(x"),
+            ("href_not_allowed", r"\href{https://example.com}{x}"),
+            ("html_not_allowed", r"\htmlClass{highlight}{x}"),
+            ("unicode_surrogate", r"\unicode{xD800}"),
+            ("unicode_unclosed", r"\unicode{x41"),
+            ("char_without_number", r"\char x"),
+            ("caret_notation_control", r"x ^^M"),
             ("no_rbrack_instead_of_bracket", r"\sqrt[3\rbrack{1}"),
+            ("invalid_macro_definition", r"\newcommand{x}{y}"),
+            ("macro_undefined_param", r"\newcommand{\x}{#1}"),
+            ("recursive_macro", r"\def\a{x\a}\a"),
+            (
+                "macro_partial_parameter",
+                r"\newcommand\h[1]{\hspace{#1em}}\h{1}",
+            ),
+            ("macro_missing_argument", r"\newcommand{\x}[1]{#1}\x"),
+            ("newcommand_builtin", r"\newcommand{\frac}{x}"),
+            ("newcommand_twice", r"\newcommand\x{a}\newcommand\x{b}"),
+            ("newcommand_after_group", r"{\newcommand\x{a}}\x"),
+            ("def_after_text_group", r"{\def\b{x}\text{a}}\b"),
+            ("invalid_operator_definition", r"\DeclareMathOperator{\x}"),
+            ("unknown_color", r"\color{rot} x"),
+            ("invalid_color_definition", r"\definecolor{c}{rgb}{1,0}"),
+            ("bbox_unknown_color", r"\bbox[yelow]{x}"),
+            ("bbox_quote", r#"\bbox[style: x" onclick="y]{x}"#),
+            (
+                "bbox_style_not_allowed",
+                r"\bbox[style:position:fixed;top:0;left:0;width:100vw;height:100vh;background:red]{x}",
+            ),
+            (
+                "bbox_border_invalid",
+                r"\bbox[border: 2px solid red; position: fixed]{x}",
+            ),
+            ("bbox_padding_invalid", r"\bbox[5px; position: fixed]{x}"),
+            ("framebox_stretch", r"\framebox[2cm][s]{x y}"),
+            ("framebox_invalid_width", r"\framebox[wide]{x}"),
+            (
+                "alignat_invalid_pairs",
+                r"\begin{alignat}{x} a \end{alignat}",
+            ),
+            (
+                "aligned_invalid_position",
+                r"\begin{aligned}[x] a \end{aligned}",
+            ),
+            (
+                "gathered_bottom",
+                r"\left\{ \begin{gathered}[b] a \\ b \end{gathered} \right.",
+            ),
+            (
+                "alignat_too_many_columns",
+                r"\begin{alignat}{1} a &= b & c &= d \end{alignat}",
+            ),
+            ("array_invalid_spec", r"\begin{array}{cx} a & b \end{array}"),
+            ("array_invalid_width", r"\begin{array}{p{2}} a \end{array}"),
+            ("array_without_columns", r"\begin{array}{||} a \end{array}"),
+            (
+                "cline_invalid_range",
+                r"\begin{array}{cc} a & b \\ \cline{2-1} \end{array}",
+            ),
+            ("hspace_without_unit", r"\hspace{3}"),
+            ("rule_invalid_width", r"\rule{wide}{1pt}"),
+            ("kern_invalid_unit", r"\kern3px"),
+            ("hspace_unclosed", r"\hspace{3pt"),
+            ("mkern_pt", r"a \mkern3pt b"),
+            ("mspace_em", r"a \mspace{1em} b"),
+            ("kern_true_em", r"a \kern 12true em b"),
+            ("smash_invalid_option", r"\smash[x]{y}"),
+            (
+                "label_invalid",
+                r#"\begin{equation} x \label{a"b} \end{equation}"#,
+            ),
+            (
+                "subarray_invalid_spec",
+                r"\begin{subarray}{r} x \end{subarray}",
+            ),
         ];
 
         for (name, problem) in problems.into_iter() {
@@ -366,4 +972,183 @@ mod tests {
             assert_snapshot!(name, &output, problem);
         }
     }
+
+    #[test]
+    fn mod_display() {
+        let mathml = latex_to_mathml(r"a \equiv b \pmod{n}", crate::Display::Block, true).unwrap();
+        assert_snapshot!("mod_display", &mathml);
+    }
+
+    #[test]
+    fn preloaded_macros() {
+        let mut macros = Macros::new();
+        macros
+            .parse_definitions(
+                r"\newcommand{\R}{\mathbb{R}} \def\abs#1{\left|#1\right|} \DeclareMathOperator\Hom{Hom}",
+            )
+            .unwrap();
+        macros.define("half", 1, None, r"\frac{#1}{2}").unwrap();
+        let options = Options {
+            macros: Some(&macros),
+            ..Default::default()
+        };
+        let mathml = latex_to_mathml_with_options(
+            r"\abs{\half x} \in \R \Hom(A, B)",
+            crate::Display::Inline,
+            true,
+            &options,
+        )
+        .unwrap();
+        assert_snapshot!("preloaded_macros", &mathml);
+        assert!(matches!(
+            macros.parse_definitions(r"\newcommand{\Hom}{H}"),
+            Err(LatexError(_, error::LatexErrKind::AlreadyDefined("Hom")))
+        ));
+    }
+
+    #[test]
+    fn links() {
+        let options = Options {
+            links: LinkPolicy {
+                schemes: &["https"],
+                fragments: true,
+            },
+            ..Default::default()
+        };
+        let problems = [
+            ("href_fragment", r"\href{#def-entropy}{H(X)}"),
+            (
+                "href_escaped",
+                r"\href{https://example.com/?a=1&b=2\#top}{x^2}",
+            ),
+            ("url", r"\url{https://example.com/~user?a=1&b=2}"),
+            (
+                "href_in_macro",
+                r"\newcommand\v[1]{\href{#1}{x}}\v{https://example.com}",
+            ),
+        ];
+        for (name, problem) in problems {
+            let mathml =
+                latex_to_mathml_with_options(problem, crate::Display::Inline, true, &options)
+                    .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
+            assert_snapshot!(name, &mathml, problem);
+        }
+        let rejected = [
+            r"\href{javascript:alert(1)}{x}",
+            r"\href{java script:alert(1)}{x}",
+            r"\href{HTTP://example.com}{x}",
+            r"\url{page.html}",
+        ];
+        for problem in rejected {
+            let result =
+                latex_to_mathml_with_options(problem, crate::Display::Inline, true, &options);
+            assert!(
+                matches!(
+                    result,
+                    Err(LatexError(0, error::LatexErrKind::LinkNotAllowed(_)))
+                ),
+                "`{}` should be rejected",
+                problem
+            );
+        }
+        assert!(options.links.allows("HTTPS://example.com"));
+    }
+
+    #[test]
+    fn html_attributes() {
+        let options = Options {
+            html_attributes: true,
+            ..Default::default()
+        };
+        let problems = [
+            ("html_class", r"\htmlClass{highlight}{x+1} = 2"),
+            ("html_id", r"\htmlId{step-1}{a}"),
+            (
+                "html_style",
+                r#"\htmlStyle{color: red; font-family: "a<b>"}{\frac{1}{2}}"#,
+            ),
+            ("html_data", r"\htmlData{step=1, hint=a&b}{x}"),
+            ("html_escape", r#"\htmlClass{a" onclick="alert(1)}{x}"#),
+            ("bbox_style", r"\bbox[style: color: blue]{x}"),
+        ];
+        for (name, problem) in problems {
+            let mathml =
+                latex_to_mathml_with_options(problem, crate::Display::Inline, true, &options)
+                    .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
+            assert_snapshot!(name, &mathml, problem);
+        }
+        let result = latex_to_mathml_with_options(
+            r#"\htmlData{x" onclick="alert(1)=1}{x}"#,
+            crate::Display::Inline,
+            true,
+            &options,
+        );
+        assert!(matches!(
+            result,
+            Err(LatexError(0, error::LatexErrKind::InvalidHtmlData(_)))
+        ));
+    }
+
+    #[test]
+    fn unit_options() {
+        let options = Options {
+            unit_options: "per-mode=fraction, output-decimal-marker={,}",
+            ..Default::default()
+        };
+        let problems = [
+            ("unit_options_global", r"\qty{1.5}{\metre\per\second}"),
+            (
+                "unit_options_overridden",
+                r"\sisetup{per-mode=symbol} \qty{1.5}{\metre\per\second}",
+            ),
+        ];
+        for (name, problem) in problems {
+            let mathml =
+                latex_to_mathml_with_options(problem, crate::Display::Inline, true, &options)
+                    .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
+            assert_snapshot!(name, &mathml, problem);
+        }
+        let options = Options {
+            unit_options: "per-mode=sideways",
+            ..Default::default()
+        };
+        let result = latex_to_mathml_with_options("x", crate::Display::Inline, true, &options);
+        assert!(matches!(
+            result,
+            Err(LatexError(0, error::LatexErrKind::InvalidUnitOption(_)))
+        ));
+    }
+
+    #[test]
+    fn equation_numbering() {
+        let problems = [
+            (
+                "align_numbered",
+                r"\begin{align} a &= b \label{eq} \\ c &= d \notag \\ e &= f \tag{*} \end{align}",
+                false,
+            ),
+            (
+                "multline_numbered",
+                r"\begin{multline} a + b \\ + c \end{multline}",
+                false,
+            ),
+            (
+                "gather_leqno",
+                r"\begin{gather} a \\ b \nonumber \end{gather}",
+                true,
+            ),
+        ];
+
+        for (name, problem, left_tags) in problems.into_iter() {
+            let options = Options {
+                equation_counter: Some(0),
+                left_tags,
+                ..Default::default()
+            };
+            let mathml =
+                latex_to_mathml_with_options(problem, crate::Display::Block, true, &options)
+                    .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
+            assert_snapshot!(name, &mathml, problem);
+        }
+    }
 }
@@ -0,0 +1,652 @@
+//! Macro expansion
+//!
+//! User-defined macros (`\newcommand`, `\renewcommand` and `\def`) and operators
+//! (`\DeclareMathOperator`) are expanded
+//! between the lexer and the parser. The expander keeps a stack of lexers: the
+//! bottom one reads the formula itself and every expansion of a macro body or
+//! of a macro argument pushes a new lexer on top of it.
+
+use crate::commands::get_command;
+use crate::error::{LatexErrKind, LatexError};
+use crate::lexer::Lexer;
+use crate::token::{TokLoc, Token};
+use crate::unicode;
+
+/// The maximal number of expansions in a single formula.
+/// This protects against infinite recursion like in `\def\a{\a}`.
+const MAX_EXPANSIONS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Definition<'a> {
+    num_args: usize,
+    /// If this is set, the first argument is optional.
+    default: Option<&'a str>,
+    body: &'a str,
+    /// If this is set, the macro was defined with `\DeclareMathOperator`, and the flag
+    /// tells whether the operator has limits (the starred form).
+    operator: Option<bool>,
+}
+
+impl Definition<'_> {
+    /// Check that the body only refers to parameters which exist.
+    fn is_valid(&self) -> bool {
+        let mut chars = self.body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '#' => match chars.next().and_then(|c| c.to_digit(10)) {
+                    Some(num) if num >= 1 && (num as usize) <= self.num_args => {}
+                    _ => return false,
+                },
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// Parse a macro definition, after the defining command has already been read.
+fn parse_definition<'a>(lexer: &mut Lexer<'a>, is_def: bool) -> Option<(&'a str, Definition<'a>)> {
+    if !is_def {
+        lexer.skip_star();
+    }
+    let name = lexer.read_command_name()?;
+    let (num_args, default) = if is_def {
+        (lexer.read_def_params()?, None)
+    } else {
+        let num_args = match lexer.read_optional_argument()? {
+            Some(num) => num.trim().parse::<usize>().ok().filter(|num| *num <= 9)?,
+            None => 0,
+        };
+        let default = if num_args > 0 {
+            lexer.read_optional_argument()?
+        } else {
+            None
+        };
+        (num_args, default)
+    };
+    let body = lexer.read_group()?;
+    let definition = Definition {
+        num_args,
+        default,
+        body,
+        operator: None,
+    };
+    definition.is_valid().then_some((name, definition))
+}
+
+/// Parse an operator definition, after `\DeclareMathOperator` has already been read.
+///
+/// The body keeps its braces, so that it can be read as the argument of `\operatorname`.
+fn parse_operator_definition<'a>(lexer: &mut Lexer<'a>) -> Option<(&'a str, Definition<'a>)> {
+    let limits = lexer.skip_star();
+    let name = lexer.read_command_name()?;
+    let body = lexer.read_braced_group()?;
+    let definition = Definition {
+        num_args: 0,
+        default: None,
+        body,
+        operator: Some(limits),
+    };
+    definition.is_valid().then_some((name, definition))
+}
+
+#[inline]
+fn defining_command(is_def: bool) -> &'static str {
+    if is_def {
+        r"\def"
+    } else {
+        r"\newcommand"
+    }
+}
+
+/// Returns `true` if `name` is a command which is built in, like `frac`.
+#[inline]
+fn is_builtin(name: &str) -> bool {
+    !matches!(get_command(name), Token::UnknownCommand(_))
+}
+
+#[derive(Debug, Clone)]
+struct OwnedDefinition {
+    name: String,
+    num_args: usize,
+    default: Option<String>,
+    body: String,
+    operator: Option<bool>,
+}
+
+/// A collection of macro definitions which can be shared between formulas.
+///
+/// This is useful if a whole document uses the same preamble.
+///
+/// ```rust
+/// use latex2mmlc::{latex_to_mathml_with_options, Display, Macros, Options};
+///
+/// let mut macros = Macros::new();
+/// macros
+///     .parse_definitions(r"\newcommand{\R}{\mathbb{R}} \newcommand{\avg}[1]{\langle #1 \rangle}")
+///     .unwrap();
+/// let options = Options {
+///     macros: Some(&macros),
+///     ..Default::default()
+/// };
+/// let mathml = latex_to_mathml_with_options(r"\avg{x} \in \R", Display::Inline, false, &options);
+/// assert!(mathml.is_ok());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Macros {
+    definitions: Vec<OwnedDefinition>,
+}
+
+impl Macros {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Define a macro with the given name (without the backslash) and body.
+    ///
+    /// The body refers to the arguments with `#1` to `#9`.
+    /// If `default` is given, the first argument is optional.
+    pub fn define<'a>(
+        &mut self,
+        name: &'a str,
+        num_args: usize,
+        default: Option<&str>,
+        body: &str,
+    ) -> Result<(), LatexError<'a>> {
+        let definition = Definition {
+            num_args,
+            default,
+            body,
+            operator: None,
+        };
+        if num_args > 9 || (default.is_some() && num_args == 0) || !definition.is_valid() {
+            return Err(LatexError(0, LatexErrKind::InvalidMacroDefinition(name)));
+        }
+        self.insert(name, definition);
+        Ok(())
+    }
+
+    /// Read all the `\newcommand`, `\renewcommand`, `\def` and `\DeclareMathOperator`
+    /// definitions in `preamble`.
+    ///
+    /// The preamble must not contain anything else but definitions.
+    /// Like in LaTeX, `\newcommand` can't redefine a command which already exists.
+    pub fn parse_definitions<'a>(&mut self, preamble: &'a str) -> Result<(), LatexError<'a>> {
+        let mut lexer = Lexer::new(preamble);
+        loop {
+            let TokLoc(loc, token) = lexer.next_token(false);
+            let is_def = match token {
+                Token::EOF => return Ok(()),
+                Token::NewCommand | Token::RenewCommand => false,
+                Token::Def => true,
+                Token::DeclareMathOperator => {
+                    match parse_operator_definition(&mut lexer) {
+                        Some((name, definition)) => self.insert(name, definition),
+                        None => {
+                            return Err(LatexError(
+                                loc,
+                                LatexErrKind::InvalidMacroDefinition(r"\DeclareMathOperator"),
+                            ))
+                        }
+                    }
+                    continue;
+                }
+                got => {
+                    return Err(LatexError(
+                        loc,
+                        LatexErrKind::UnexpectedToken {
+                            expected: &Token::NewCommand,
+                            got,
+                        },
+                    ))
+                }
+            };
+            match parse_definition(&mut lexer, is_def) {
+                Some((name, _))
+                    if matches!(token, Token::NewCommand)
+                        && (is_builtin(name) || self.get(name).is_some()) =>
+                {
+                    return Err(LatexError(loc, LatexErrKind::AlreadyDefined(name)))
+                }
+                Some((name, definition)) => self.insert(name, definition),
+                None => {
+                    return Err(LatexError(
+                        loc,
+                        LatexErrKind::InvalidMacroDefinition(defining_command(is_def)),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, name: &str, definition: Definition) {
+        let owned = OwnedDefinition {
+            name: name.to_string(),
+            num_args: definition.num_args,
+            default: definition.default.map(str::to_string),
+            body: definition.body.to_string(),
+            operator: definition.operator,
+        };
+        match self.definitions.iter_mut().find(|def| def.name == name) {
+            Some(existing) => *existing = owned,
+            None => self.definitions.push(owned),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<Definition<'_>> {
+        self.definitions
+            .iter()
+            .find(|def| def.name == name)
+            .map(|def| Definition {
+                num_args: def.num_args,
+                default: def.default.as_deref(),
+                body: &def.body,
+                operator: def.operator,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Argument<'source> {
+    text: &'source str,
+    /// The arguments of the macro in whose body this argument appeared.
+    /// These are needed to resolve parameters like `#1` within the argument.
+    args: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Frame<'source> {
+    lexer: Lexer<'source>,
+    /// Index of the first argument in the argument list, if this frame has arguments.
+    args: Option<usize>,
+    /// Location of the macro invocation which created this frame.
+    /// All tokens of the frame are reported at this location.
+    loc: Option<usize>,
+    /// If this is set, the end of the frame is reported as the end of the input,
+    /// and the frame is only removed by `pop_fragment`.
+    fragment: bool,
+}
+
+pub(crate) struct MacroExpander<'source> {
+    /// The stack of lexers; the first one reads the original input.
+    frames: Vec<Frame<'source>>,
+    args: Vec<Argument<'source>>,
+    definitions: Vec<(&'source str, Definition<'source>)>,
+    /// The number of definitions at the start of each open group. Like in TeX, definitions
+    /// are local to the group in which they appear, so they are dropped at its end.
+    scopes: Vec<usize>,
+    macros: Option<&'source Macros>,
+    expansions: usize,
+    error: Option<LatexError<'source>>,
+    pub input_length: usize,
+    pub text_mode: bool,
+}
+
+impl<'source> MacroExpander<'source> {
+    pub(crate) fn new(lexer: Lexer<'source>, macros: Option<&'source Macros>) -> Self {
+        MacroExpander {
+            input_length: lexer.input_length,
+            frames: vec![Frame {
+                lexer,
+                args: None,
+                loc: None,
+                fragment: false,
+            }],
+            args: Vec::new(),
+            definitions: Vec::new(),
+            scopes: Vec::new(),
+            macros,
+            expansions: 0,
+            error: None,
+            text_mode: false,
+        }
+    }
+
+    #[inline]
+    fn top(&mut self) -> &mut Frame<'source> {
+        // The first frame is never popped.
+        let last = self.frames.len() - 1;
+        &mut self.frames[last]
+    }
+
+    #[inline]
+    fn has_macros(&self) -> bool {
+        !self.definitions.is_empty() || self.macros.is_some_and(|m| !m.is_empty())
+    }
+
+    fn lookup(&self, name: &str) -> Option<Definition<'source>> {
+        // Later definitions take precedence.
+        match self.definitions.iter().rev().find(|(n, _)| *n == name) {
+            Some((_, definition)) => Some(*definition),
+            None => self.macros.and_then(|macros| macros.get(name)),
+        }
+    }
+
+    /// Forget the definitions of the innermost group, which has just been closed.
+    fn end_scope(&mut self) {
+        if let Some(len) = self.scopes.pop() {
+            self.definitions.truncate(len);
+        }
+    }
+
+    /// Generate the next token with all macros expanded.
+    pub(crate) fn next_token(&mut self, wants_digit: bool) -> TokLoc<'source> {
+        loop {
+            let has_macros = self.has_macros();
+            let text_mode = self.text_mode;
+            let depth = self.frames.len() - 1;
+            let frame = self.top();
+            frame.lexer.text_mode = text_mode;
+            let frame_loc = frame.loc;
+            let is_fragment = frame.fragment;
+
+            if let Some(base) = frame.args {
+                if let Some(num) = frame.lexer.read_macro_param() {
+                    // The parameter numbers have been checked when the macro was defined.
+                    let Argument { text, args } = self.args[base + num - 1];
+                    if let Err(e) = self.push_frame(text, args, frame_loc.unwrap_or(0)) {
+                        return self.fail(e);
+                    }
+                    continue;
+                }
+            }
+
+            if has_macros {
+                if let Some(definition) = frame
+                    .lexer
+                    .upcoming_command()
+                    .and_then(|name| self.lookup(name))
+                {
+                    let frame = self.top();
+                    let TokLoc(loc, _) = frame.lexer.next_token(false);
+                    let loc = frame.loc.unwrap_or(loc);
+                    if let Err(e) = self.expand(definition, loc) {
+                        return self.fail(e);
+                    }
+                    if let Some(limits) = definition.operator {
+                        // The body is read as the argument of `\operatorname`.
+                        return TokLoc(loc, Token::OperatorName(limits));
+                    }
+                    continue;
+                }
+            }
+
+            let frame = self.top();
+            let TokLoc(loc, token) = frame.lexer.next_token(wants_digit);
+            let loc = frame.loc.unwrap_or(loc);
+            let token = match self.substitute_token(token) {
+                Ok(token) => token,
+                Err(kind) => return self.fail(LatexError(loc, kind)),
+            };
+            match token {
+                Token::EOF if depth > 0 && !is_fragment => {
+                    self.frames.pop();
+                }
+                Token::NewCommand | Token::RenewCommand | Token::Def => {
+                    let is_def = matches!(token, Token::Def);
+                    match parse_definition(&mut self.top().lexer, is_def) {
+                        Some((name, _))
+                            if matches!(token, Token::NewCommand)
+                                && (is_builtin(name) || self.lookup(name).is_some()) =>
+                        {
+                            return self.fail(LatexError(loc, LatexErrKind::AlreadyDefined(name)))
+                        }
+                        Some(definition) => self.definitions.push(definition),
+                        None => {
+                            return self.fail(LatexError(
+                                loc,
+                                LatexErrKind::InvalidMacroDefinition(defining_command(is_def)),
+                            ))
+                        }
+                    }
+                }
+                Token::DeclareMathOperator => {
+                    match parse_operator_definition(&mut self.top().lexer) {
+                        Some(definition) => self.definitions.push(definition),
+                        None => {
+                            return self.fail(LatexError(
+                                loc,
+                                LatexErrKind::InvalidMacroDefinition(r"\DeclareMathOperator"),
+                            ))
+                        }
+                    }
+                }
+                // Environments are groups as well.
+                Token::GroupBegin | Token::Begin => {
+                    self.scopes.push(self.definitions.len());
+                    return TokLoc(loc, token);
+                }
+                Token::GroupEnd | Token::End => {
+                    self.end_scope();
+                    return TokLoc(loc, token);
+                }
+                token => return TokLoc(loc, token),
+            }
+        }
+    }
+
+    fn fail(&mut self, error: LatexError<'source>) -> TokLoc<'source> {
+        let loc = error.0;
+        self.error.get_or_insert(error);
+        TokLoc(loc, Token::MacroError)
+    }
+
+    /// Return the error which occurred during macro expansion.
+    pub(crate) fn take_error(&mut self, loc: usize) -> LatexError<'source> {
+        self.error
+            .take()
+            .unwrap_or(LatexError(loc, LatexErrKind::UnexpectedEOF))
+    }
+
+    fn push_frame(
+        &mut self,
+        text: &'source str,
+        args: Option<usize>,
+        loc: usize,
+    ) -> Result<(), LatexError<'source>> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(LatexError(loc, LatexErrKind::MacroExpansionLimit));
+        }
+        self.frames.push(Frame {
+            lexer: Lexer::new(text),
+            args,
+            loc: Some(loc),
+            fragment: false,
+        });
+        Ok(())
+    }
+
+    /// Read `text` before the rest of the input, as if it were a formula of its own.
+    ///
+    /// This is used for the math in commands like `\ce`, which have their own syntax.
+    /// The end of `text` is reported as the end of the input, until `pop_fragment` is called.
+    pub(crate) fn push_fragment(
+        &mut self,
+        text: &'source str,
+        loc: usize,
+    ) -> Result<(), LatexError<'source>> {
+        self.push_frame(text, None, loc)?;
+        self.top().fragment = true;
+        Ok(())
+    }
+
+    /// Continue with the rest of the input after a fragment.
+    pub(crate) fn pop_fragment(&mut self) {
+        debug_assert!(self.top().fragment);
+        self.frames.pop();
+    }
+
+    fn expand(
+        &mut self,
+        definition: Definition<'source>,
+        loc: usize,
+    ) -> Result<(), LatexError<'source>> {
+        let base = self.args.len();
+        if let Some(default) = definition.default {
+            let arg = match self.read_optional_argument(loc)? {
+                Some(arg) => arg,
+                None => Argument {
+                    text: default,
+                    args: None,
+                },
+            };
+            self.args.push(arg);
+        }
+        while self.args.len() - base < definition.num_args {
+            let arg = self.read_argument(loc)?;
+            self.args.push(arg);
+        }
+        let args = if definition.num_args > 0 {
+            Some(base)
+        } else {
+            None
+        };
+        self.push_frame(definition.body, args, loc)
+    }
+
+    /// Drop all frames which have been read completely, except for the first one.
+    /// This allows macro arguments to continue after the end of a macro body.
+    fn drop_exhausted_frames(&mut self) {
+        while self.frames.len() > 1 && !self.top().fragment && self.top().lexer.is_exhausted() {
+            self.frames.pop();
+        }
+    }
+
+    fn read_argument(&mut self, loc: usize) -> Result<Argument<'source>, LatexError<'source>> {
+        self.drop_exhausted_frames();
+        let frame = self.top();
+        frame.lexer.text_mode = false;
+        match frame.lexer.read_argument() {
+            Some(text) => Ok(Argument {
+                text,
+                args: frame.args,
+            }),
+            None if frame.lexer.is_exhausted() => Err(LatexError(loc, LatexErrKind::UnexpectedEOF)),
+            None => Err(LatexError(
+                loc,
+                LatexErrKind::UnexpectedClose(Token::GroupEnd),
+            )),
+        }
+    }
+
+    fn read_optional_argument(
+        &mut self,
+        loc: usize,
+    ) -> Result<Option<Argument<'source>>, LatexError<'source>> {
+        self.drop_exhausted_frames();
+        let frame = self.top();
+        frame.lexer.text_mode = false;
+        match frame.lexer.read_optional_argument() {
+            Some(text) => Ok(text.map(|text| Argument {
+                text,
+                args: frame.args,
+            })),
+            None => Err(LatexError(
+                loc,
+                LatexErrKind::UnclosedGroup(Token::SquareBracketClose),
+            )),
+        }
+    }
+
+    /// Replace a parameter like `#1` in raw text by the argument of the macro.
+    ///
+    /// Raw text, like the argument of `\color` or `\ce`, is read without any expansion,
+    /// so a parameter can only be replaced if it makes up the whole text.
+    fn substitute(&self, mut text: &'source str) -> Result<&'source str, LatexErrKind<'source>> {
+        let mut args = self.frames[self.frames.len() - 1].args;
+        while let Some(base) = args {
+            let mut lexer = Lexer::new(text);
+            match lexer.read_macro_param() {
+                Some(num) if lexer.is_exhausted() => {
+                    // The parameter numbers have been checked when the macro was defined.
+                    let argument = self.args[base + num - 1];
+                    text = argument.text;
+                    args = argument.args;
+                }
+                _ if has_param(text) => return Err(LatexErrKind::UnexpandableParameter(text)),
+                _ => break,
+            }
+        }
+        Ok(text)
+    }
+
+    /// Replace parameters in the raw text of the tokens which the lexer reads in one go.
+    fn substitute_token(
+        &self,
+        token: Token<'source>,
+    ) -> Result<Token<'source>, LatexErrKind<'source>> {
+        Ok(match token {
+            Token::Dimension(dimension) => Token::Dimension(self.substitute(dimension)?),
+            Token::MathDimension(dimension) => Token::MathDimension(self.substitute(dimension)?),
+            Token::InvalidCharCode(code) => {
+                let code = self.substitute(code)?;
+                unicode::parse_char_code(code)
+                    .map_or(Token::InvalidCharCode(code), unicode::classify)
+            }
+            token => token,
+        })
+    }
+
+    /// Read text until the next `}`.
+    #[inline]
+    pub(crate) fn read_text_content(
+        &mut self,
+    ) -> Option<Result<&'source str, LatexErrKind<'source>>> {
+        let text = self.top().lexer.read_text_content()?;
+        // The `}` has been read together with the text.
+        self.end_scope();
+        Some(self.substitute(text))
+    }
+
+    /// Read everything until the `}` which closes the group.
+    #[inline]
+    pub(crate) fn read_brace_content(
+        &mut self,
+    ) -> Option<Result<&'source str, LatexErrKind<'source>>> {
+        let text = self.top().lexer.read_brace_content()?;
+        // The `}` has been read together with the content.
+        self.end_scope();
+        Some(self.substitute(text))
+    }
+
+    /// Read text until the next `]`.
+    #[inline]
+    pub(crate) fn read_bracket_content(
+        &mut self,
+    ) -> Option<Result<&'source str, LatexErrKind<'source>>> {
+        let text = self.top().lexer.read_bracket_content()?;
+        Some(self.substitute(text))
+    }
+
+    /// Return the text until the next `]`, without consuming anything.
+    #[inline]
+    pub(crate) fn peek_bracket_content(
+        &mut self,
+    ) -> Option<Result<&'source str, LatexErrKind<'source>>> {
+        let text = self.top().lexer.peek_bracket_content()?;
+        Some(self.substitute(text))
+    }
+}
+
+/// Returns `true` if the text refers to a parameter like `#1`.
+fn has_param(text: &str) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '#' if chars.next().is_some_and(|c| c.is_ascii_digit()) => return true,
+            _ => {}
+        }
+    }
+    false
+}
@@ -6,6 +6,14 @@ use serde::Serialize;
 #[repr(transparent)]
 pub struct Op(char);
 
+impl Op {
+    /// Use any character as an operator, like in `\mathbin{\#}`.
+    #[inline]
+    pub(crate) const fn from_char(c: char) -> Self {
+        Op(c)
+    }
+}
+
 impl From<Op> for char {
     #[inline]
     fn from(op: Op) -> Self {
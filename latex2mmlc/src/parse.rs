@@ -4,29 +4,54 @@ use crate::{
     arena::{Arena, Buffer, NodeList, NodeListBuilder, NodeRef, SingletonOrList, StringBuilder},
     ast::Node,
     attribute::{
-        Accent, Align, FracAttr, MathSpacing, MathVariant, OpAttr, ParenAttr, Stretchy, Style,
-        TextTransform,
+        Accent, Align, FracAttr, HtmlAttr, Limits, LineStyle, MathClass, MathSpacing, MathVariant,
+        Notation, OpAttr, Padding, ParenAttr, Stretchy, Style, TagPosition, TextTransform,
     },
+    chem::{self, ChemArrow, ChemItem},
+    color::{self, Color},
     commands::get_negated_op,
+    dimension,
     error::{LatexErrKind, LatexError, Place},
-    lexer::Lexer,
-    ops,
+    macros::MacroExpander,
+    ops::{self, Op},
     token::{TokLoc, Token},
+    units::{self, Digits, Number, PerMode, Uncertainty, Unit, UnitOptions},
+    EquationNumber, LinkPolicy,
 };
 
 pub(crate) struct Parser<'arena, 'source> {
-    l: Lexer<'source>,
+    l: MacroExpander<'source>,
     peek: TokLoc<'source>,
     buffer: Buffer,
     arena: &'arena Arena,
     tf: Option<TextTransform>,
     var: Option<MathVariant>,
+    /// Colors defined with `\definecolor`.
+    colors: Vec<(&'source str, Color)>,
+    /// The options for units and quantities, which can be changed with `\sisetup`.
+    unit_options: UnitOptions,
+    /// The links which are allowed in `\href` and `\url`.
+    links: LinkPolicy<'source>,
+    /// Whether commands like `\htmlClass` are allowed.
+    html_attributes: bool,
+    /// Whether the formula is shown in display mode, which affects some spacing.
+    display: bool,
+    /// The equation counter; equations are only numbered if this is set.
+    equation_counter: Option<u32>,
+    tag_position: TagPosition,
+    /// Whether the next node may be `\tag`, `\notag` or `\label`, because it is directly
+    /// in a row of a display environment or of the whole formula.
+    tags_allowed: bool,
+    /// The keys of all `\label`s, which have to be unique.
+    labels: Vec<&'source str>,
+    /// The equation numbers which were assigned automatically.
+    pub(crate) equation_numbers: Vec<EquationNumber>,
 }
 impl<'arena, 'source> Parser<'arena, 'source>
 where
     'source: 'arena, // The reference to the source string will live as long as the arena.
 {
-    pub(crate) fn new(l: Lexer<'source>, arena: &'arena Arena) -> Self {
+    pub(crate) fn new(l: MacroExpander<'source>, arena: &'arena Arena) -> Self {
         let input_length = l.input_length;
         let mut p = Parser {
             l,
@@ -35,6 +60,16 @@ where
             arena,
             tf: None,
             var: None,
+            colors: Vec::new(),
+            unit_options: UnitOptions::default(),
+            links: LinkPolicy::default(),
+            html_attributes: false,
+            display: false,
+            equation_counter: None,
+            tag_position: TagPosition::Right,
+            tags_allowed: false,
+            labels: Vec::new(),
+            equation_numbers: Vec::new(),
         };
         // Discard the EOF token we just stored in `peek_token`.
         // This loads the first real token into `peek_token`.
@@ -42,6 +77,37 @@ where
         p
     }
 
+    pub(crate) fn set_display(&mut self, display: bool) {
+        self.display = display;
+    }
+
+    pub(crate) fn set_link_policy(&mut self, links: LinkPolicy<'source>) {
+        self.links = links;
+    }
+
+    pub(crate) fn set_html_attributes(&mut self, allowed: bool) {
+        self.html_attributes = allowed;
+    }
+
+    /// Apply unit options like `per-mode=fraction`, as if they were given to `\sisetup`.
+    pub(crate) fn set_unit_options(
+        &mut self,
+        options: &'source str,
+    ) -> Result<(), LatexError<'source>> {
+        self.unit_options
+            .apply(options)
+            .map_err(|option| LatexError(0, LatexErrKind::InvalidUnitOption(option)))
+    }
+
+    pub(crate) fn set_equation_numbering(&mut self, counter: Option<u32>, left_tags: bool) {
+        self.equation_counter = counter;
+        self.tag_position = if left_tags {
+            TagPosition::Left
+        } else {
+            TagPosition::Right
+        };
+    }
+
     fn next_token(&mut self) -> TokLoc<'source> {
         let peek_token = self.l.next_token(self.peek.token().acts_on_a_digit());
         // Return the previous peek token and store the new peek token.
@@ -49,18 +115,55 @@ where
     }
 
     pub(crate) fn parse(&mut self) -> Result<Node<'arena>, LatexError<'source>> {
+        let content = self.parse_rows(Token::EOF)?;
+        if !content.iter().any(|node| matches!(node, Node::Tag(_))) {
+            return Ok(Node::PseudoRow(content));
+        }
+        // A tag outside of an environment applies to the whole formula.
+        let table = self.commit(Node::Table {
+            content,
+            align: Align::Center,
+            attr: Some(FracAttr::DisplayStyleFullWidth),
+            tags: Some(self.tag_position),
+        });
         let mut list_builder = NodeListBuilder::new();
+        list_builder.push(table);
+        Ok(Node::PseudoRow(list_builder.finish()))
+    }
 
-        loop {
-            let cur_tokloc = self.next_token();
-            if matches!(cur_tokloc.token(), Token::EOF) {
-                break;
+    /// Parse the rows of a display environment like `align`, or of the whole formula,
+    /// where `\tag`, `\notag` and `\label` may be used.
+    fn parse_rows(
+        &mut self,
+        end_token: Token<'source>,
+    ) -> Result<NodeList<'arena>, LatexError<'source>> {
+        let mut nodes = NodeListBuilder::new();
+        // Whether the current row already has a tag.
+        let mut tagged = false;
+
+        while !self.peek.token().is_same_kind(&end_token) {
+            let next = self.next_token();
+            let loc = next.location();
+            if matches!(next.token(), Token::EOF) {
+                return Err(LatexError(loc, LatexErrKind::UnclosedGroup(end_token)));
+            }
+            self.tags_allowed = true;
+            let node = if nodes.is_empty() {
+                self.parse_first_node(next)?
+            } else {
+                self.parse_node(next)?
+            };
+            match node.node() {
+                Node::Tag(_) if tagged => {
+                    return Err(LatexError(loc, LatexErrKind::MultipleTags));
+                }
+                Node::Tag(_) => tagged = true,
+                Node::RowSeparator => tagged = false,
+                _ => {}
             }
-            let node = self.parse_node(cur_tokloc)?;
-            list_builder.push(node);
+            nodes.push(node);
         }
-
-        Ok(Node::PseudoRow(list_builder.finish()))
+        Ok(nodes.finish())
     }
 
     fn parse_node(
@@ -68,23 +171,127 @@ where
         cur_tokloc: TokLoc<'source>,
     ) -> Result<NodeRef<'arena>, LatexError<'source>> {
         let target = self.parse_single_node(cur_tokloc)?;
+        let bounds = self.get_bounds()?;
+        Ok(self.attach_bounds(target, bounds))
+    }
 
-        match self.get_bounds()? {
-            Bounds(Some(sub), Some(sup)) => Ok(self.commit(Node::SubSup {
+    fn attach_bounds(&self, target: NodeRef<'arena>, bounds: Bounds<'arena>) -> NodeRef<'arena> {
+        match bounds {
+            Bounds(Some(sub), Some(sup)) => self.commit(Node::SubSup {
                 target: target.node(),
                 sub,
                 sup,
-            })),
-            Bounds(Some(symbol), None) => Ok(self.commit(Node::Subscript {
+            }),
+            Bounds(Some(symbol), None) => self.commit(Node::Subscript {
                 target: target.node(),
                 symbol,
-            })),
-            Bounds(None, Some(symbol)) => Ok(self.commit(Node::Superscript {
+            }),
+            Bounds(None, Some(symbol)) => self.commit(Node::Superscript {
                 target: target.node(),
                 symbol,
-            })),
-            Bounds(None, None) => Ok(target),
+            }),
+            Bounds(None, None) => target,
+        }
+    }
+
+    /// Parse the first node of a row or group.
+    ///
+    /// Here, scripts on an empty group like in `{}^{14}_{6}\mathrm{C}` are the prescripts
+    /// of the following node.
+    fn parse_first_node(
+        &mut self,
+        cur_tokloc: TokLoc<'source>,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        if !matches!(cur_tokloc.token(), Token::GroupBegin)
+            || !matches!(self.peek.token(), Token::GroupEnd)
+        {
+            return self.parse_node(cur_tokloc);
+        }
+        let empty = self.parse_single_node(cur_tokloc)?;
+        let (sub, sup) = self.get_script_refs()?;
+        if (sub.is_none() && sup.is_none())
+            || matches!(
+                self.peek.token(),
+                Token::EOF
+                    | Token::GroupEnd
+                    | Token::End
+                    | Token::Right
+                    | Token::Ampersand
+                    | Token::NewLine
+            )
+        {
+            // There is nothing the scripts could belong to.
+            let bounds = Bounds(sub.map(|s| s.node()), sup.map(|s| s.node()));
+            return Ok(self.attach_bounds(empty, bounds));
+        }
+        let mut pre = NodeListBuilder::new();
+        self.push_script_pair(&mut pre, sub, sup);
+        let token = self.next_token();
+        let base = self.parse_single_node(token)?.node();
+        let mut post = NodeListBuilder::new();
+        self.parse_script_pair(&mut post)?;
+        Ok(self.commit(Node::Multiscript {
+            base,
+            pre: pre.finish(),
+            post: post.finish(),
+        }))
+    }
+
+    /// Parse a subscript and a superscript, and append them as a pair to `scripts`.
+    fn parse_script_pair(
+        &mut self,
+        scripts: &mut NodeListBuilder<'arena>,
+    ) -> Result<(), LatexError<'source>> {
+        let (sub, sup) = self.get_script_refs()?;
+        self.push_script_pair(scripts, sub, sup);
+        Ok(())
+    }
+
+    /// Append a subscript and a superscript as a pair to `scripts`.
+    /// A missing script is replaced by an empty row, but nothing is appended if there
+    /// are no scripts at all.
+    fn push_script_pair(
+        &self,
+        scripts: &mut NodeListBuilder<'arena>,
+        sub: Option<NodeRef<'arena>>,
+        sup: Option<NodeRef<'arena>>,
+    ) {
+        if sub.is_none() && sup.is_none() {
+            return;
+        }
+        for script in [sub, sup] {
+            scripts.push(script.unwrap_or_else(|| {
+                self.commit(Node::Row {
+                    nodes: NodeList::empty(),
+                    style: None,
+                })
+            }));
+        }
+    }
+
+    /// Parse the scripts in one of the groups of `\sideset`, like `{_a^b_c^d}`.
+    fn parse_sideset_group(&mut self) -> Result<NodeList<'arena>, LatexError<'source>> {
+        self.check_lbrace()?;
+        self.next_token(); // Discard the opening brace.
+        let mut scripts = NodeListBuilder::new();
+        while !matches!(self.peek.token(), Token::GroupEnd) {
+            if !matches!(
+                self.peek.token(),
+                Token::Underscore | Token::Circumflex | Token::Prime
+            ) {
+                let TokLoc(loc, token) = self.next_token();
+                return Err(LatexError(
+                    loc,
+                    LatexErrKind::UnexpectedToken {
+                        expected: &Token::GroupEnd,
+                        got: token,
+                    },
+                ));
+            }
+            self.parse_script_pair(&mut scripts)?;
         }
+        self.next_token(); // Discard the closing brace.
+        Ok(scripts.finish())
     }
 
     /// Put the node onto the heap in the arena and return a reference to it.
@@ -108,6 +315,17 @@ where
         cur_tokloc: TokLoc<'source>,
     ) -> Result<NodeRef<'arena>, LatexError<'source>> {
         let TokLoc(loc, cur_token) = cur_tokloc;
+        // Anything within this node, like an argument, is not directly in a row.
+        let tags_allowed = mem::replace(&mut self.tags_allowed, false);
+        if matches!(cur_token, Token::Tag | Token::NoTag | Token::Label) && !tags_allowed {
+            return Err(LatexError(
+                loc,
+                LatexErrKind::CannotBeUsedHere {
+                    got: cur_token,
+                    correct_place: Place::InDisplayRow,
+                },
+            ));
+        }
         let node = match cur_token {
             Token::Number(number) => match self.tf {
                 Some(tf) => {
@@ -138,6 +356,8 @@ where
                 self.tf.as_ref().map_or(x, |tf| tf.transform(x, false)),
                 self.var,
             ),
+            // Outside of `\tag`, a dollar sign is an ordinary symbol.
+            Token::Dollar => Node::SingleLetterIdent('$', self.var),
             Token::UprightLetter(x) => match self.tf.as_ref() {
                 Some(tf) => Node::SingleLetterIdent(tf.transform(x, true), None),
                 None => Node::SingleLetterIdent(x, Some(MathVariant::Normal)),
@@ -146,8 +366,20 @@ where
             Token::OpGreaterThan => Node::OpGreaterThan,
             Token::OpLessThan => Node::OpLessThan,
             Token::OpAmpersand => Node::OpAmpersand,
-            Token::Function(fun) => Node::MultiLetterIdent(fun),
+            Token::Function(fun) => {
+                if matches!(self.peek.token(), Token::Limits(_)) {
+                    return self.parse_limits(Limits::Never, |limits| operator_name(fun, limits));
+                }
+                Node::MultiLetterIdent(fun)
+            }
             Token::Space(space) => Node::Space(space),
+            Token::Dimension(dimension) => Node::Space(self.convert_dimension(loc, dimension)?),
+            Token::MathDimension(dimension) => {
+                let Some(length) = dimension::parse_math_dimension(dimension) else {
+                    return Err(LatexError(loc, LatexErrKind::ExpectedMathUnits(dimension)));
+                };
+                Node::Space(self.css_length(length))
+            }
             Token::NonBreakingSpace | Token::Whitespace => Node::Text("\u{A0}"),
             Token::SingleSpace => Node::Text(" "),
             Token::Sqrt => {
@@ -243,6 +475,47 @@ where
                     Node::Underset { symbol, target }
                 }
             }
+            Token::OverUnderset | Token::UnderOverset => {
+                let first = self.parse_token()?;
+                let second = self.parse_token()?;
+                let target = self.parse_single_token()?;
+                let (over, under) = if matches!(cur_token, Token::OverUnderset) {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                Node::UnderOver {
+                    target,
+                    under,
+                    over,
+                }
+            }
+            Token::Stackrel(class) => {
+                // Like in the `stackrel` package, there may be a symbol below in brackets.
+                let under = if matches!(self.peek.token(), Token::SquareBracketOpen) {
+                    self.next_token(); // Discard the opening bracket.
+                    let under = self.parse_group(Token::SquareBracketClose)?;
+                    self.next_token(); // Discard the closing bracket.
+                    Some(self.squeeze(under, None).node())
+                } else {
+                    None
+                };
+                let over = self.parse_token()?;
+                let target = self.parse_single_token()?;
+                let stacked = self.commit(match under {
+                    Some(under) => Node::UnderOver {
+                        target,
+                        under,
+                        over,
+                    },
+                    None => Node::Overset {
+                        symbol: over,
+                        target,
+                    },
+                });
+                // The stacked symbol keeps the spacing of a relation or binary operator.
+                self.with_class(stacked, class)
+            }
             Token::OverUnderBrace(x, is_over) => {
                 let target = self.parse_single_token()?;
                 let symbol = self.commit(Node::Operator(x, None)).node();
@@ -272,43 +545,35 @@ where
                 }
             }
             Token::BigOp(op) => {
-                let target = if matches!(self.peek.token(), Token::Limits) {
-                    self.next_token(); // Discard the limits token.
-                    self.commit(Node::Operator(op, Some(OpAttr::NoMovableLimits)))
+                return self.parse_limits(Limits::Display, |limits| {
+                    let attr =
+                        matches!(limits, Some(Limits::Always)).then_some(OpAttr::NoMovableLimits);
+                    Node::Operator(op, attr)
+                });
+            }
+            Token::Class(class) => {
+                let token = self.next_token();
+                let content = self.parse_single_node(token)?;
+                let node = self.with_class(content, class);
+                if !matches!(class, MathClass::Op) {
+                    node
                 } else {
-                    self.commit(Node::Operator(op, None))
-                };
-                match self.get_bounds()? {
-                    Bounds(Some(under), Some(over)) => Node::UnderOver {
-                        target: target.node(),
-                        under,
-                        over,
-                    },
-                    Bounds(Some(symbol), None) => Node::Underset {
-                        target: target.node(),
-                        symbol,
-                    },
-                    Bounds(None, Some(symbol)) => Node::Overset {
-                        target: target.node(),
-                        symbol,
-                    },
-                    Bounds(None, None) => {
-                        return Ok(target);
-                    }
+                    // Like a big operator, `\mathop` has its limits below and above.
+                    return self.parse_limits(Limits::Display, |limits| match node {
+                        Node::OperatorWithSpacing {
+                            op, left, right, ..
+                        } => Node::OperatorWithSpacing {
+                            op,
+                            left,
+                            right,
+                            attr: movable_limits(limits),
+                        },
+                        node => node,
+                    });
                 }
             }
             Token::Lim(lim) => {
-                let lim = self.commit(Node::MultiLetterIdent(lim));
-                if matches!(self.peek.token(), Token::Underscore) {
-                    self.next_token(); // Discard the underscore token.
-                    let under = self.parse_single_token()?;
-                    Node::Underset {
-                        target: lim.node(),
-                        symbol: under,
-                    }
-                } else {
-                    return Ok(lim);
-                }
+                return self.parse_limits(Limits::Display, |limits| operator_name(lim, limits));
             }
             Token::Slashed => {
                 // TODO: Actually check the braces.
@@ -360,49 +625,20 @@ where
                 }
                 return Ok(node_ref);
             }
+            Token::FontSwitch(tf, var) => {
+                let old_var = mem::replace(&mut self.var, var);
+                let old_tf = mem::replace(&mut self.tf, tf);
+                let content = self.parse_rest_of_scope();
+                self.var = old_var;
+                self.tf = old_tf;
+                return Ok(self.merge_single_letters(content?, None));
+            }
             Token::Integral(int) => {
-                if matches!(self.peek.token(), Token::Limits) {
-                    self.next_token(); // Discard the limits token.
-                    let target = self.commit(Node::Operator(int, None));
-                    match self.get_bounds()? {
-                        Bounds(Some(under), Some(over)) => Node::UnderOver {
-                            target: target.node(),
-                            under,
-                            over,
-                        },
-                        Bounds(Some(symbol), None) => Node::Underset {
-                            target: target.node(),
-                            symbol,
-                        },
-                        Bounds(None, Some(symbol)) => Node::Overset {
-                            target: target.node(),
-                            symbol,
-                        },
-                        Bounds(None, None) => {
-                            return Ok(target);
-                        }
-                    }
-                } else {
-                    let target = self.commit(Node::Operator(int, None));
-                    match self.get_bounds()? {
-                        Bounds(Some(sub), Some(sup)) => Node::SubSup {
-                            target: target.node(),
-                            sub,
-                            sup,
-                        },
-                        Bounds(Some(symbol), None) => Node::Subscript {
-                            target: target.node(),
-                            symbol,
-                        },
-                        Bounds(None, Some(symbol)) => Node::Superscript {
-                            target: target.node(),
-                            symbol,
-                        },
-                        Bounds(None, None) => {
-                            return Ok(target);
-                        }
-                    }
-                }
+                return self.parse_limits(Limits::Never, |limits| {
+                    let attr =
+                        matches!(limits, Some(Limits::Display)).then_some(OpAttr::MovableLimits);
+                    Node::Operator(int, attr)
+                });
             }
             Token::Colon => match &self.peek.token() {
                 Token::Operator(ops::EQUALS_SIGN) => {
@@ -416,11 +652,13 @@ where
                         op: ops::COLON,
                         left: Some(MathSpacing::FourMu),
                         right: Some(MathSpacing::Zero),
+                        attr: None,
                     });
                     let second = self.commit(Node::OperatorWithSpacing {
                         op,
                         left: Some(MathSpacing::Zero),
                         right: None,
+                        attr: None,
                     });
                     Node::PseudoRow(NodeList::from_two_nodes(first, second))
                 }
@@ -428,6 +666,7 @@ where
                     op: ops::COLON,
                     left: Some(MathSpacing::FourMu),
                     right: Some(MathSpacing::FourMu),
+                    attr: None,
                 },
             },
             Token::GroupBegin => {
@@ -551,13 +790,126 @@ where
                 self.check_lbrace()?;
                 // Read the environment name.
                 let env_name = self.parse_text_group()?;
-                let content = self.parse_group(Token::End)?.finish();
+                // Read the arguments of the environment, which come before the content.
+                if matches!(env_name, "aligned" | "alignedat" | "gathered") {
+                    self.parse_position()?;
+                }
+                let mut subarray_align = None;
+                let mut columns = None;
+                let mut max_columns = None;
+                match env_name {
+                    // The `subarray` environment takes the column alignment as an argument.
+                    "subarray" => {
+                        self.check_lbrace()?;
+                        let spec = self.parse_text_group()?;
+                        subarray_align = match spec.trim() {
+                            "c" => Some(Align::CompactCenter),
+                            "l" => Some(Align::CompactLeft),
+                            _ => {
+                                return Err(LatexError(
+                                    loc,
+                                    LatexErrKind::InvalidColumnSpec(env_name),
+                                ))
+                            }
+                        };
+                    }
+                    "array" | "darray" => columns = Some(self.parse_column_spec(env_name)?),
+                    // The argument is the number of column pairs, which limits the columns.
+                    "alignat" | "alignat*" | "alignedat" => {
+                        self.check_lbrace()?;
+                        let pairs = self.parse_text_group()?;
+                        match pairs.trim().parse::<usize>() {
+                            Ok(n) if n > 0 => max_columns = Some(2 * n),
+                            _ => {
+                                return Err(LatexError(
+                                    loc,
+                                    LatexErrKind::InvalidColumnSpec(env_name),
+                                ))
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                let content = if matches!(
+                    env_name,
+                    "equation"
+                        | "equation*"
+                        | "multline"
+                        | "multline*"
+                        | "align"
+                        | "align*"
+                        | "alignat"
+                        | "alignat*"
+                        | "flalign"
+                        | "flalign*"
+                        | "gather"
+                        | "gather*"
+                ) {
+                    self.parse_rows(Token::End)?
+                } else {
+                    self.parse_group(Token::End)?.finish()
+                };
                 let end_token_loc = self.next_token().location();
+                if let Some(max_columns) = max_columns {
+                    let mut column = 1;
+                    for node in content.iter() {
+                        match node {
+                            Node::ColumnSeparator => column += 1,
+                            Node::RowSeparator => column = 1,
+                            _ => {}
+                        }
+                        if column > max_columns {
+                            return Err(LatexError(loc, LatexErrKind::TooManyColumns(env_name)));
+                        }
+                    }
+                }
+                // Display environments can have tags, and the ones without `*` are numbered.
+                let (content, tags) = match env_name {
+                    "equation" | "multline" => self.number_rows(content, true, true),
+                    "align" | "alignat" | "flalign" | "gather" => {
+                        self.number_rows(content, true, false)
+                    }
+                    "equation*" | "multline*" => self.number_rows(content, false, true),
+                    "align*" | "alignat*" | "flalign*" | "gather*" => {
+                        self.number_rows(content, false, false)
+                    }
+                    _ => (content, None),
+                };
+                // With tags, the table takes up the full width.
+                let display_attr = Some(match tags {
+                    Some(_) => FracAttr::DisplayStyleFullWidth,
+                    None => FracAttr::DisplayStyleTrue,
+                });
                 let node = match env_name {
-                    "align" | "align*" | "aligned" => Node::Table {
+                    "align" | "align*" | "aligned" | "split" => Node::Table {
+                        content,
+                        align: Align::Alternating,
+                        attr: display_attr,
+                        tags,
+                    },
+                    "alignat" | "alignat*" | "alignedat" => Node::Table {
+                        content,
+                        align: Align::AlternatingTight,
+                        attr: display_attr,
+                        tags,
+                    },
+                    "flalign" | "flalign*" => Node::Table {
                         content,
                         align: Align::Alternating,
-                        attr: Some(FracAttr::DisplayStyleTrue),
+                        attr: Some(FracAttr::DisplayStyleFullWidth),
+                        tags,
+                    },
+                    "gather" | "gather*" | "gathered" | "equation" | "equation*" => Node::Table {
+                        content,
+                        align: Align::Center,
+                        attr: display_attr,
+                        tags,
+                    },
+                    "multline" | "multline*" => Node::Table {
+                        content,
+                        align: Align::Multline,
+                        attr: Some(FracAttr::DisplayStyleFullWidth),
+                        tags,
                     },
                     "cases" => {
                         let align = Align::Left;
@@ -566,6 +918,7 @@ where
                                 content,
                                 align,
                                 attr: None,
+                                tags,
                             })
                             .node();
                         Node::Fenced {
@@ -580,6 +933,18 @@ where
                         content,
                         align: Align::Center,
                         attr: None,
+                        tags,
+                    },
+                    "array" | "darray" => Node::Array {
+                        content,
+                        columns: columns.unwrap_or(NodeList::empty()),
+                        style: (env_name == "darray").then_some(Style::DisplayStyle),
+                    },
+                    "subarray" => Node::Table {
+                        content,
+                        align: subarray_align.unwrap_or(Align::CompactCenter),
+                        attr: Some(FracAttr::ScriptStyle),
+                        tags,
                     },
                     matrix_variant
                     @ ("pmatrix" | "bmatrix" | "Bmatrix" | "vmatrix" | "Vmatrix") => {
@@ -602,6 +967,7 @@ where
                                     content,
                                     align,
                                     attr,
+                                    tags,
                                 })
                                 .node(),
                             style: None,
@@ -626,7 +992,9 @@ where
 
                 node
             }
-            Token::OperatorName => {
+            Token::OperatorName(with_limits) => {
+                // `\operatorname*` has its limits below and above, like `\lim`.
+                let with_limits = self.parse_star() || with_limits;
                 // TODO: Don't parse a node just to immediately destructure it.
                 let node = self.parse_single_token()?;
                 let mut builder = self.buffer.get_builder();
@@ -636,121 +1004,1854 @@ where
                         LatexErrKind::ExpectedText("\\operatorname"),
                     ));
                 }
-                Node::MultiLetterIdent(builder.finish(self.arena))
+                let name = builder.finish(self.arena);
+                let default = if with_limits {
+                    Limits::Display
+                } else {
+                    Limits::Never
+                };
+                return self.parse_limits(default, |limits| operator_name(name, limits));
             }
-            Token::Text(transform) => {
-                self.l.text_mode = true;
-                let node = self.parse_single_token()?;
-                let mut builder = self.buffer.get_builder();
-                if !extract_letters(&mut builder, node, transform) {
-                    return Err(LatexError(loc, LatexErrKind::ExpectedText("\\text")));
+            Token::Text(transform) => Node::Text(self.parse_text(loc, transform, "\\text")?),
+            Token::Ampersand => Node::ColumnSeparator,
+            Token::NewLine => Node::RowSeparator,
+            Token::Mathstrut => {
+                // `\mathstrut` is the same as `\vphantom{(}`.
+                let content = self
+                    .commit(Node::Operator(
+                        ops::LEFT_PARENTHESIS,
+                        Some(OpAttr::StretchyFalse),
+                    ))
+                    .node();
+                Node::Padded {
+                    content,
+                    padding: Padding::VPhantom,
                 }
-                let text = builder.finish(self.arena);
-                self.l.text_mode = false;
-                // Discard any whitespace tokens that are still stored in self.peek_token.
-                if matches!(self.peek.token(), Token::Whitespace) {
-                    self.next_token();
+            }
+            Token::Chemistry(is_unit) => {
+                self.check_lbrace()?;
+                let content = self.parse_raw_group()?;
+                return self.parse_chemistry(loc, content, is_unit);
+            }
+            Token::Num => {
+                let options = self.parse_unit_options(loc)?;
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                let number = units::parse_number(input)
+                    .ok_or(LatexError(loc, LatexErrKind::InvalidNumber(input)))?;
+                let mut nodes = NodeListBuilder::new();
+                self.push_number(&mut nodes, &number, &options);
+                return Ok(self.squeeze(nodes, None));
+            }
+            Token::Unit => {
+                let options = self.parse_unit_options(loc)?;
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                let units = units::parse_units(input)
+                    .map_err(|unit| LatexError(loc, LatexErrKind::UnknownUnit(unit)))?;
+                let mut nodes = NodeListBuilder::new();
+                self.push_units(&mut nodes, &units, &options);
+                return Ok(self.squeeze(nodes, None));
+            }
+            Token::Quantity if self.is_physics_quantity() => {
+                // The physics package uses `\qty` for automatically sized delimiters,
+                // like in `\qty(\frac{a}{b})`.
+                let stretchy = !self.parse_star();
+                let TokLoc(loc, next_token) = self.next_token();
+                let (open, close) = match next_token {
+                    Token::Paren(ops::LEFT_PARENTHESIS, ..) => {
+                        (ops::LEFT_PARENTHESIS, ops::RIGHT_PARENTHESIS)
+                    }
+                    Token::Paren(ops::LEFT_CURLY_BRACKET, ..) => {
+                        (ops::LEFT_CURLY_BRACKET, ops::RIGHT_CURLY_BRACKET)
+                    }
+                    Token::Paren(ops::VERTICAL_LINE, ..) => {
+                        (ops::VERTICAL_LINE, ops::VERTICAL_LINE)
+                    }
+                    Token::SquareBracketOpen => {
+                        (ops::LEFT_SQUARE_BRACKET, ops::RIGHT_SQUARE_BRACKET)
+                    }
+                    _ => {
+                        return Err(LatexError(
+                            loc,
+                            LatexErrKind::MissingParenthesis {
+                                location: &Token::Quantity,
+                                got: next_token,
+                            },
+                        ))
+                    }
+                };
+                let content = self.parse_delimited(close, None)?;
+                return Ok(self.fence(open, close, content, stretchy));
+            }
+            Token::Quantity => {
+                let options = self.parse_unit_options(loc)?;
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                let number = units::parse_number(input)
+                    .ok_or(LatexError(loc, LatexErrKind::InvalidNumber(input)))?;
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                let units = units::parse_units(input)
+                    .map_err(|unit| LatexError(loc, LatexErrKind::UnknownUnit(unit)))?;
+                let mut nodes = NodeListBuilder::new();
+                self.push_number(&mut nodes, &number, &options);
+                if !units[0].is_unspaced() {
+                    nodes.push(self.commit(Node::Space(MathSpacing::ThreeMu.into())));
                 }
-                Node::Text(text)
+                self.push_units(&mut nodes, &units, &options);
+                return Ok(self.squeeze(nodes, None));
             }
-            Token::Ampersand => Node::ColumnSeparator,
-            Token::NewLine => Node::RowSeparator,
-            Token::Mathstrut => Node::Mathstrut,
-            Token::Style(style) => {
-                let content = self.parse_group(Token::GroupEnd)?;
+            Token::Angle => {
+                let options = self.parse_unit_options(loc)?;
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                let angle = units::parse_angle(input)
+                    .ok_or(LatexError(loc, LatexErrKind::InvalidNumber(input)))?;
+                let mut nodes = NodeListBuilder::new();
+                for (number, symbol) in angle {
+                    self.push_number(&mut nodes, &number, &options);
+                    nodes.push(self.commit(upright_letters(symbol)));
+                }
+                return Ok(self.squeeze(nodes, None));
+            }
+            Token::UnitSetup => {
+                self.check_lbrace()?;
+                let input = self.parse_raw_group()?;
+                self.unit_options
+                    .apply(input)
+                    .map_err(|option| LatexError(loc, LatexErrKind::InvalidUnitOption(option)))?;
+                // The setup itself doesn't produce any output, but if it is used as
+                // an argument, the argument must not be missing.
                 Node::Row {
-                    nodes: content.finish(),
-                    style: Some(style),
+                    nodes: NodeList::empty(),
+                    style: None,
                 }
             }
-            Token::UnknownCommand(name) => {
-                return Err(LatexError(loc, LatexErrKind::UnknownCommand(name)));
+            Token::Delimited(open, close) => {
+                let stretchy = !self.parse_star();
+                let content = self.parse_next()?;
+                return Ok(self.fence(open, close, content, stretchy));
             }
-            // Token::Underscore | Token::Circumflex => {
-            Token::Circumflex => {
-                return Err(LatexError(
-                    loc,
-                    LatexErrKind::CannotBeUsedHere {
-                        got: cur_token,
-                        correct_place: Place::AfterOpOrIdent,
-                    },
-                ));
+            Token::BraKet(open, close) => {
+                let stretchy = !self.parse_star();
+                // Like after `\middle`, a bar in a bra or ket has the size of the delimiters.
+                let bar = Some(if stretchy {
+                    OpAttr::StretchyTrue
+                } else {
+                    OpAttr::StretchyFalse
+                });
+                self.check_lbrace()?;
+                self.next_token(); // Discard the opening brace.
+                let mut content = self.parse_delimited_group(bar)?;
+                // `\braket{a}{b}` is the same as `\braket{a|b}`.
+                if close == ops::MATHEMATICAL_RIGHT_ANGLE_BRACKET
+                    && open == ops::MATHEMATICAL_LEFT_ANGLE_BRACKET
+                    && matches!(self.peek.token(), Token::GroupBegin)
+                {
+                    self.next_token(); // Discard the opening brace.
+                    let ket = self.parse_delimited_group(bar)?;
+                    let mut nodes = NodeListBuilder::new();
+                    nodes.push(content);
+                    nodes.push(self.commit(Node::Operator(ops::VERTICAL_LINE, bar)));
+                    nodes.push(ket);
+                    content = self.squeeze(nodes, None);
+                }
+                return Ok(self.fence(open, close, content, stretchy));
             }
-            Token::Prime => {
-                let target = self
-                    .commit(Node::Row {
-                        nodes: NodeList::empty(),
-                        style: None,
-                    })
-                    .node();
-                let symbol = self.commit(Node::Operator(ops::PRIME, None)).node();
-                Node::Superscript { target, symbol }
+            Token::Derivative(partial) => {
+                // The starred form is a slashed fraction, like `df/dx`.
+                let slashed = self.parse_star();
+                let order = self.parse_optional_text_group()?;
+                let function = self.parse_next()?;
+                let (function, variables) = if matches!(self.peek.token(), Token::GroupBegin) {
+                    let mut variables = vec![self.parse_next()?];
+                    // A mixed partial derivative, like `\pdv{f}{x}{y}`.
+                    if partial && matches!(self.peek.token(), Token::GroupBegin) {
+                        variables.push(self.parse_next()?);
+                    }
+                    (Some(function), variables)
+                } else {
+                    // Only the operator, like `\dv{x}`.
+                    (None, vec![function])
+                };
+                let order = if variables.len() > 1 {
+                    Some("2")
+                } else {
+                    order.filter(|order| !order.trim().is_empty())
+                };
+                let d = if partial {
+                    ops::PARTIAL_DIFFERENTIAL
+                } else {
+                    'd'
+                };
+                let mut num = NodeListBuilder::new();
+                num.push(self.differential(loc, d, order)?);
+                if let Some(function) = function {
+                    num.push(function);
+                }
+                let mut den = NodeListBuilder::new();
+                let one_variable = variables.len() == 1;
+                for variable in variables {
+                    den.push(self.differential(loc, d, None)?);
+                    match order {
+                        Some(order) if one_variable => {
+                            let symbol = self.parse_math_fragment(loc, order)?.node();
+                            den.push(self.commit(Node::Superscript {
+                                target: variable.node(),
+                                symbol,
+                            }));
+                        }
+                        _ => den.push(variable),
+                    }
+                }
+                if slashed {
+                    let mut nodes = NodeListBuilder::new();
+                    nodes.push(self.squeeze(num, None));
+                    nodes.push(self.commit(Node::Operator(ops::SOLIDUS, None)));
+                    nodes.push(self.squeeze(den, None));
+                    return Ok(self.squeeze(nodes, None));
+                }
+                Node::Frac {
+                    num: self.squeeze(num, None).node(),
+                    den: self.squeeze(den, None).node(),
+                    lt: None,
+                    attr: None,
+                }
             }
-            Token::Underscore => {
-                let sub = self.parse_single_token()?;
-                let base = self.parse_single_token()?;
-                Node::Multiscript { base, sub }
+            Token::Evaluated => {
+                let stretchy = !self.parse_star();
+                // Besides `\eval{x}_0^1`, the content may start with a delimiter and end
+                // with the bar, like in `\eval(x|_0^1`.
+                let open = match self.peek.token() {
+                    Token::Paren(ops::LEFT_PARENTHESIS, ..) => Some(ops::LEFT_PARENTHESIS),
+                    Token::SquareBracketOpen => Some(ops::LEFT_SQUARE_BRACKET),
+                    _ => None,
+                };
+                let content = match open {
+                    Some(_) => {
+                        self.next_token(); // Discard the opening delimiter.
+                        self.parse_delimited(ops::VERTICAL_LINE, None)?
+                    }
+                    None => self.parse_next()?,
+                };
+                // The scripts of the bar follow, and are attached like for any other node.
+                return Ok(self.fence(
+                    open.unwrap_or(ops::NULL),
+                    ops::VERTICAL_LINE,
+                    content,
+                    stretchy,
+                ));
             }
-            Token::Limits => {
-                return Err(LatexError(
-                    loc,
-                    LatexErrKind::CannotBeUsedHere {
-                        got: cur_token,
-                        correct_place: Place::AfterBigOp,
-                    },
-                ))
+            Token::Order => {
+                let stretchy = !self.parse_star();
+                let content = self.parse_next()?;
+                let mut nodes = NodeListBuilder::new();
+                nodes.push(self.commit(Node::SingleLetterIdent('𝒪', None)));
+                nodes.push(self.fence(
+                    ops::LEFT_PARENTHESIS,
+                    ops::RIGHT_PARENTHESIS,
+                    content,
+                    stretchy,
+                ));
+                return Ok(self.squeeze(nodes, None));
             }
-            Token::EOF => return Err(LatexError(loc, LatexErrKind::UnexpectedEOF)),
-            Token::End | Token::Right | Token::GroupEnd => {
-                return Err(LatexError(loc, LatexErrKind::UnexpectedClose(cur_token)))
+            Token::VectorBold => {
+                // `\vb*` is for vectors in bold italic, like Greek letters.
+                let tf = if self.parse_star() {
+                    TextTransform::BoldItalic
+                } else {
+                    TextTransform::Bold
+                };
+                return self.parse_single_node(TokLoc(loc, Token::Transform(Some(tf), None)));
             }
-        };
-        Ok(self.commit(node))
-    }
-
+            Token::Bmod => Node::BinaryOperatorName("mod", MathSpacing::FiveMu),
+            Token::Pmod | Token::Pod | Token::Mod => {
+                // The spacing follows amsmath, where it is larger in display mode.
+                let mut nodes = NodeListBuilder::new();
+                let space = match (&cur_token, self.display) {
+                    (_, true) => "1em",
+                    (Token::Mod, false) => "0.6667em",
+                    (_, false) => "0.4444em",
+                };
+                nodes.push(self.commit(Node::Space(space)));
+                if !matches!(cur_token, Token::Mod) {
+                    nodes.push(self.commit(Node::Operator(
+                        ops::LEFT_PARENTHESIS,
+                        Some(OpAttr::StretchyFalse),
+                    )));
+                }
+                if !matches!(cur_token, Token::Pod) {
+                    nodes.push(self.commit(Node::MultiLetterIdent("mod")));
+                    nodes.push(self.commit(Node::Space("0.3333em")));
+                }
+                let token = self.next_token();
+                nodes.push(self.parse_single_node(token)?);
+                if !matches!(cur_token, Token::Mod) {
+                    nodes.push(self.commit(Node::Operator(
+                        ops::RIGHT_PARENTHESIS,
+                        Some(OpAttr::StretchyFalse),
+                    )));
+                }
+                Node::PseudoRow(nodes.finish())
+            }
+            Token::Rule => {
+                let raise = match self.parse_optional_text_group()? {
+                    Some(raise) => Some(self.convert_dimension(loc, raise)?),
+                    None => None,
+                };
+                self.check_lbrace()?;
+                let width = self.parse_text_group()?;
+                let width = self.convert_dimension(loc, width)?;
+                self.check_lbrace()?;
+                let height = self.parse_text_group()?;
+                let height = self.convert_dimension(loc, height)?;
+                Node::Rule {
+                    width,
+                    height,
+                    depth: None,
+                    raise,
+                }
+            }
+            Token::Strut => {
+                // `\strut` is an invisible rule with the height and depth of a line,
+                // which are 70% and 30% of the `\baselineskip` of 1.2em.
+                Node::Rule {
+                    width: "0",
+                    height: "0.84em",
+                    depth: Some("0.36em"),
+                    raise: None,
+                }
+            }
+            Token::Padded(padding) => {
+                let content = self.parse_single_token()?;
+                Node::Padded { content, padding }
+            }
+            Token::Smash => {
+                let padding = match self.parse_optional_text_group()?.map(str::trim) {
+                    None | Some("tb" | "bt") => Padding::Smash,
+                    Some("t") => Padding::SmashTop,
+                    Some("b") => Padding::SmashBottom,
+                    Some(option) => {
+                        return Err(LatexError(loc, LatexErrKind::InvalidSmashOption(option)));
+                    }
+                };
+                let content = self.parse_single_token()?;
+                Node::Padded { content, padding }
+            }
+            Token::Style(style) => {
+                // Like in TeX, the style lasts until the end of the scope,
+                // and the spacing which depends on display mode follows it.
+                let display = mem::replace(&mut self.display, style == Style::DisplayStyle);
+                let content = self.parse_rest_of_scope();
+                self.display = display;
+                Node::Row {
+                    nodes: content?,
+                    style: Some(style),
+                }
+            }
+            Token::Color => {
+                let color = self.parse_color()?;
+                let content = self.parse_rest_of_scope()?;
+                Node::Colored { color, content }
+            }
+            Token::TextColor => {
+                let color = self.parse_color()?;
+                let content = self.parse_single_node_as_list()?;
+                Node::Colored { color, content }
+            }
+            Token::ColorBox | Token::FColorBox => {
+                let frame = if matches!(cur_token, Token::FColorBox) {
+                    Some(self.parse_color()?)
+                } else {
+                    None
+                };
+                let background = self.parse_color()?;
+                let style = match frame {
+                    Some(frame) => {
+                        let mut builder = self.buffer.get_builder();
+                        builder.push_str(FBOX_STYLE);
+                        builder.push_char(' ');
+                        builder.extend(frame.hex().into_iter());
+                        builder.finish(self.arena)
+                    }
+                    None => FBOX_PADDING,
+                };
+                let text = self.parse_text(loc, None, "\\colorbox")?;
+                Node::Framed {
+                    content: self.commit(Node::Text(text)).node(),
+                    background: Some(background),
+                    style,
+                }
+            }
+            Token::Boxed => {
+                // `\boxed` puts its argument into display style, like `\fbox{$\displaystyle ...$}`.
+                let nodes = self.parse_single_node_as_list()?;
+                let content = self
+                    .commit(Node::Row {
+                        nodes,
+                        style: Some(Style::DisplayStyle),
+                    })
+                    .node();
+                Node::Framed {
+                    content,
+                    background: None,
+                    style: FBOX_STYLE,
+                }
+            }
+            Token::FBox => {
+                let text = self.parse_text(loc, None, "\\fbox")?;
+                Node::Framed {
+                    content: self.commit(Node::Text(text)).node(),
+                    background: None,
+                    style: FBOX_STYLE,
+                }
+            }
+            Token::FrameBox => {
+                let width = self.parse_optional_text_group()?;
+                let align = match self.parse_optional_text_group()?.map(str::trim) {
+                    None | Some("c") => "",
+                    Some("l") => ALIGN_LEFT,
+                    Some("r") => ALIGN_RIGHT,
+                    Some(position) => {
+                        return Err(LatexError(loc, LatexErrKind::InvalidBoxPosition(position)))
+                    }
+                };
+                let text = self.parse_text(loc, None, "\\framebox")?;
+                let content = self.commit(Node::Text(text));
+                match width {
+                    Some(width) => {
+                        // The content can only be aligned within a given width in a table
+                        // cell, so the box becomes a table with a single framed cell.
+                        // Like in LaTeX, the width includes the padding and the frame.
+                        let width = self.convert_dimension(loc, width)?;
+                        let mut builder = self.buffer.get_builder();
+                        if !align.is_empty() {
+                            builder.push_str(align);
+                            builder.push_str("; ");
+                        }
+                        builder.push_str("width: ");
+                        builder.push_str(width);
+                        builder.push_str("; box-sizing: border-box; ");
+                        builder.push_str(FBOX_STYLE);
+                        let style = builder.finish(self.arena);
+                        let mut columns = NodeListBuilder::new();
+                        columns.push(self.commit(Node::ArrayColumn {
+                            style,
+                            material: None,
+                        }));
+                        let mut cell = NodeListBuilder::new();
+                        cell.push(content);
+                        Node::Array {
+                            content: cell.finish(),
+                            columns: columns.finish(),
+                            style: None,
+                        }
+                    }
+                    None => Node::Framed {
+                        content: content.node(),
+                        background: None,
+                        style: FBOX_STYLE,
+                    },
+                }
+            }
+            Token::BBox => {
+                let options = self.parse_optional_text_group()?.unwrap_or("");
+                let mut background = None;
+                let mut builder = self.buffer.get_builder();
+                let mut separator = "";
+                for option in options.split(',').map(str::trim) {
+                    if option.is_empty() {
+                        continue;
+                    }
+                    // The options end up in an attribute, so they must not be able to close it.
+                    if option.contains(['"', '<', '>', '&']) {
+                        return Err(LatexError(loc, LatexErrKind::InvalidBBoxOption(option)));
+                    }
+                    if let Some(border) = option.strip_prefix("border") {
+                        // Only a width, a line style and a color are accepted, so that
+                        // the border can't smuggle in other CSS properties.
+                        builder.push_str(separator);
+                        builder.push_str("border:");
+                        for part in border.trim_start_matches([' ', ':']).split_whitespace() {
+                            builder.push_char(' ');
+                            if matches!(part, "solid" | "dashed" | "dotted" | "double") {
+                                builder.push_str(part);
+                            } else if let Some(width) = parse_bbox_length(part) {
+                                push_length(&mut builder, width);
+                            } else if let Some(color) = get_color(&self.colors, part) {
+                                builder.extend(color.hex().into_iter());
+                            } else {
+                                return Err(LatexError(
+                                    loc,
+                                    LatexErrKind::InvalidBBoxOption(option),
+                                ));
+                            }
+                        }
+                    } else if let Some(style) = option.strip_prefix("style:") {
+                        // Arbitrary CSS is only allowed if the user trusts the input.
+                        if !self.html_attributes {
+                            return Err(LatexError(
+                                loc,
+                                LatexErrKind::HtmlNotAllowed(r"\bbox[style:...]"),
+                            ));
+                        }
+                        builder.push_str(separator);
+                        builder.push_str(style.trim());
+                    } else if option.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                        let Some(padding) = parse_bbox_length(option) else {
+                            return Err(LatexError(loc, LatexErrKind::InvalidDimension(option)));
+                        };
+                        builder.push_str(separator);
+                        builder.push_str("padding: ");
+                        push_length(&mut builder, padding);
+                    } else {
+                        let Some(color) = get_color(&self.colors, option) else {
+                            return Err(LatexError(loc, LatexErrKind::UnknownColor(option)));
+                        };
+                        background = Some(color);
+                        continue;
+                    }
+                    separator = "; ";
+                }
+                let style = builder.finish(self.arena);
+                let content = self.parse_single_token()?;
+                Node::Framed {
+                    content,
+                    background,
+                    style,
+                }
+            }
+            Token::Href | Token::Url => {
+                self.check_lbrace()?;
+                let url = self.parse_raw_group()?;
+                // Like in hyperref, special characters may be escaped, like in `\#def`.
+                let href = if url.contains('\\') {
+                    let mut builder = self.buffer.get_builder();
+                    let mut chars = url.chars();
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '\\' => builder.extend(chars.next().into_iter()),
+                            c => builder.push_char(c),
+                        }
+                    }
+                    builder.finish(self.arena)
+                } else {
+                    url
+                };
+                if !self.links.allows(href) {
+                    return Err(LatexError(loc, LatexErrKind::LinkNotAllowed(url)));
+                }
+                let content = if matches!(cur_token, Token::Href) {
+                    self.parse_single_node_as_list()?
+                } else {
+                    // The text is not escaped when it is emitted.
+                    let mut builder = self.buffer.get_builder();
+                    for c in href.chars() {
+                        match c {
+                            '&' => builder.push_str("&amp;"),
+                            '<' => builder.push_str("&lt;"),
+                            '>' => builder.push_str("&gt;"),
+                            c => builder.push_char(c),
+                        }
+                    }
+                    let text = builder.finish(self.arena);
+                    let mut nodes = NodeListBuilder::new();
+                    nodes.push(self.commit(Node::Text(text)));
+                    nodes.finish()
+                };
+                Node::Link { href, content }
+            }
+            Token::Html(attr) => {
+                if !self.html_attributes {
+                    let command = match attr {
+                        HtmlAttr::Class => "\\htmlClass",
+                        HtmlAttr::Id => "\\htmlId",
+                        HtmlAttr::Style => "\\htmlStyle",
+                        HtmlAttr::Data => "\\htmlData",
+                    };
+                    return Err(LatexError(loc, LatexErrKind::HtmlNotAllowed(command)));
+                }
+                self.check_lbrace()?;
+                let value = self.parse_raw_group()?.trim();
+                if matches!(attr, HtmlAttr::Data) && !is_valid_html_data(value) {
+                    return Err(LatexError(loc, LatexErrKind::InvalidHtmlData(value)));
+                }
+                let content = self.parse_single_node_as_list()?;
+                Node::HtmlAttribute {
+                    attr,
+                    value,
+                    content,
+                }
+            }
+            Token::DefineColor => {
+                self.check_lbrace()?;
+                let name = self.parse_text_group()?;
+                self.check_lbrace()?;
+                let model = self.parse_text_group()?;
+                self.check_lbrace()?;
+                let spec_loc = self.peek.location();
+                let spec = self.parse_text_group()?;
+                let Some(color) = color::parse_color_model(model, spec) else {
+                    return Err(LatexError(spec_loc, LatexErrKind::UnknownColor(spec)));
+                };
+                self.colors.push((name.trim(), color));
+                // The definition itself doesn't produce any output, but if it is used as
+                // an argument, the argument must not be missing.
+                Node::Row {
+                    nodes: NodeList::empty(),
+                    style: None,
+                }
+            }
+            Token::Enclose(notation) => {
+                let content = self.parse_single_token()?;
+                Node::Enclose { content, notation }
+            }
+            Token::CancelTo => {
+                let symbol = self.parse_token()?;
+                let content = self.parse_single_token()?;
+                let target = self
+                    .commit(Node::Enclose {
+                        content,
+                        notation: Notation::UpDiagonalArrow,
+                    })
+                    .node();
+                Node::Superscript { target, symbol }
+            }
+            Token::XArrow(op) => {
+                let below = if matches!(self.peek.token(), Token::SquareBracketOpen) {
+                    self.next_token(); // Discard the opening token.
+                    let below = self.parse_group(Token::SquareBracketClose)?;
+                    self.next_token(); // Discard the closing token.
+                    Some(self.squeeze(below, None).node())
+                } else {
+                    None
+                };
+                let above = self.parse_token()?;
+                let arrow = self.commit(Node::Operator(op, Some(OpAttr::StretchyTrue)));
+                Node::XArrow {
+                    arrow: arrow.node(),
+                    above,
+                    below,
+                }
+            }
+            Token::SubStack => {
+                self.check_lbrace()?;
+                self.next_token(); // Discard the opening token.
+                let content = self.parse_group(Token::GroupEnd)?.finish();
+                self.next_token(); // Discard the closing token.
+                Node::Table {
+                    content,
+                    align: Align::CompactCenter,
+                    attr: Some(FracAttr::ScriptStyle),
+                    tags: None,
+                }
+            }
+            Token::HorizontalRule(line) => Node::HorizontalRule {
+                line,
+                columns: None,
+            },
+            Token::CLine => {
+                self.check_lbrace()?;
+                let range = self.parse_text_group()?;
+                let columns = range
+                    .split_once('-')
+                    .and_then(|(first, last)| {
+                        Some((first.trim().parse().ok()?, last.trim().parse().ok()?))
+                    })
+                    .filter(|&(first, last)| 0 < first && first <= last);
+                let Some(columns) = columns else {
+                    return Err(LatexError(loc, LatexErrKind::InvalidCLine(range)));
+                };
+                Node::HorizontalRule {
+                    line: LineStyle::Solid,
+                    columns: Some(columns),
+                }
+            }
+            Token::Tag => {
+                // `\tag*` omits the parentheses.
+                let starred = matches!(self.peek.token(), Token::Operator(ops::ASTERISK));
+                if starred {
+                    self.next_token();
+                }
+                Node::Tag(self.parse_tag(loc, starred)?)
+            }
+            Token::NoTag => Node::NoTag,
+            Token::Label => {
+                self.check_lbrace()?;
+                let key = self.parse_text_group()?;
+                // The key becomes an attribute value, so it must not contain markup.
+                if key.is_empty() || key.contains(['"', '&', '<', '>']) {
+                    return Err(LatexError(loc, LatexErrKind::InvalidLabel(key)));
+                }
+                // The key becomes the `id` of the row, which has to be unique.
+                if self.labels.contains(&key) {
+                    return Err(LatexError(loc, LatexErrKind::DuplicateLabel(key)));
+                }
+                self.labels.push(key);
+                Node::Label(key)
+            }
+            Token::UnknownCommand(name) => {
+                return Err(LatexError(loc, LatexErrKind::UnknownCommand(name)));
+            }
+            Token::InvalidCharCode(code) => {
+                return Err(LatexError(loc, LatexErrKind::InvalidCharCode(code)));
+            }
+            // The lexer reads the dimension right away and returns it as `Token::Dimension`,
+            // and it turns character codes into the token of the character.
+            Token::Kern
+            | Token::Skip
+            | Token::MathKern
+            | Token::MathSkip
+            | Token::Char
+            | Token::Unicode => unreachable!(),
+            // Macro definitions are consumed by the macro expander,
+            // so we only see them here if something went wrong.
+            Token::MacroError
+            | Token::NewCommand
+            | Token::RenewCommand
+            | Token::Def
+            | Token::DeclareMathOperator => {
+                return Err(self.l.take_error(loc));
+            }
+            // Token::Underscore | Token::Circumflex => {
+            Token::Circumflex => {
+                return Err(LatexError(
+                    loc,
+                    LatexErrKind::CannotBeUsedHere {
+                        got: cur_token,
+                        correct_place: Place::AfterOpOrIdent,
+                    },
+                ));
+            }
+            Token::Prime => {
+                let target = self
+                    .commit(Node::Row {
+                        nodes: NodeList::empty(),
+                        style: None,
+                    })
+                    .node();
+                let symbol = self.commit(Node::Operator(ops::PRIME, None)).node();
+                Node::Superscript { target, symbol }
+            }
+            Token::Underscore => {
+                let token = self.next_token();
+                let sub = self.parse_single_node(token)?;
+                let empty = self.commit(Node::Row {
+                    nodes: NodeList::empty(),
+                    style: None,
+                });
+                let base = self.parse_single_token()?;
+                Node::Multiscript {
+                    base,
+                    pre: NodeList::from_two_nodes(sub, empty),
+                    post: NodeList::empty(),
+                }
+            }
+            Token::Sideset => {
+                let pre = self.parse_sideset_group()?;
+                let post = self.parse_sideset_group()?;
+                let token = self.next_token();
+                if let Token::BigOp(op) = token.token() {
+                    // The limits of a big operator go below and above the whole construct.
+                    let attr = matches!(self.peek.token(), Token::Limits(Limits::Always))
+                        .then_some(OpAttr::NoMovableLimits);
+                    let base = self.commit(Node::Operator(*op, attr)).node();
+                    return self.parse_limits(Limits::Display, |_| Node::Multiscript {
+                        base,
+                        pre,
+                        post,
+                    });
+                }
+                let base = self.parse_single_node(token)?.node();
+                Node::Multiscript { base, pre, post }
+            }
+            Token::Prescript => {
+                let token = self.next_token();
+                let sup = self.parse_single_node(token)?;
+                let token = self.next_token();
+                let sub = self.parse_single_node(token)?;
+                let base = self.parse_single_token()?;
+                Node::Multiscript {
+                    base,
+                    pre: NodeList::from_two_nodes(sub, sup),
+                    post: NodeList::empty(),
+                }
+            }
+            Token::Limits(_) => {
+                return Err(LatexError(
+                    loc,
+                    LatexErrKind::CannotBeUsedHere {
+                        got: cur_token,
+                        correct_place: Place::AfterBigOp,
+                    },
+                ))
+            }
+            Token::EOF => return Err(LatexError(loc, LatexErrKind::UnexpectedEOF)),
+            Token::End | Token::Right | Token::GroupEnd => {
+                return Err(LatexError(loc, LatexErrKind::UnexpectedClose(cur_token)))
+            }
+        };
+        Ok(self.commit(node))
+    }
+
     #[inline]
     fn parse_token(&mut self) -> Result<&'arena Node<'arena>, LatexError<'source>> {
         let token = self.next_token();
         self.parse_node(token).map(|n| n.node())
     }
 
-    #[inline]
-    fn parse_single_token(&mut self) -> Result<&'arena Node<'arena>, LatexError<'source>> {
-        let token = self.next_token();
-        self.parse_single_node(token).map(|n| n.node())
+    /// Like `parse_single_token`, but the node can still be put into a list.
+    #[inline]
+    fn parse_next(&mut self) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let token = self.next_token();
+        self.parse_single_node(token)
+    }
+
+    #[inline]
+    fn parse_single_token(&mut self) -> Result<&'arena Node<'arena>, LatexError<'source>> {
+        let token = self.next_token();
+        self.parse_single_node(token).map(|n| n.node())
+    }
+
+    /// Parse the contents of a group which can contain any expression.
+    fn parse_group(
+        &mut self,
+        end_token: Token<'source>,
+    ) -> Result<NodeListBuilder<'arena>, LatexError<'source>> {
+        let mut nodes = NodeListBuilder::new();
+
+        while !self.peek.token().is_same_kind(&end_token) {
+            let next = self.next_token();
+            if matches!(next.token(), Token::EOF) {
+                // When the input ends without the closing token.
+                return Err(LatexError(
+                    next.location(),
+                    LatexErrKind::UnclosedGroup(end_token),
+                ));
+            }
+            let node = if nodes.is_empty() {
+                self.parse_first_node(next)?
+            } else {
+                self.parse_node(next)?
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// Parse the contents of a group which can only contain text.
+    fn parse_text_group(&mut self) -> Result<&'source str, LatexError<'source>> {
+        let result = self.l.read_text_content();
+        // Discard the opening token (which is still stored as `peek`).
+        let opening_loc = self.next_token().location();
+        result
+            .ok_or(LatexErrKind::UnclosedGroup(Token::GroupEnd))
+            .and_then(|text| text)
+            .map_err(|kind| LatexError(opening_loc, kind))
+    }
+
+    /// Parse the contents of a group as they are, including any commands.
+    ///
+    /// This is used for commands like `\ce`, which have their own syntax.
+    fn parse_raw_group(&mut self) -> Result<&'source str, LatexError<'source>> {
+        let result = self.l.read_brace_content();
+        // Discard the opening token (which is still stored as `peek`).
+        let opening_loc = self.next_token().location();
+        result
+            .ok_or(LatexErrKind::UnclosedGroup(Token::GroupEnd))
+            .and_then(|text| text)
+            .map_err(|kind| LatexError(opening_loc, kind))
+    }
+
+    /// Parse the contents of an optional argument in square brackets which can only
+    /// contain text. Returns `None` if there is no optional argument.
+    fn parse_optional_text_group(&mut self) -> Result<Option<&'source str>, LatexError<'source>> {
+        if !matches!(self.peek.token(), Token::SquareBracketOpen) {
+            return Ok(None);
+        }
+        let result = self.l.read_bracket_content();
+        // Discard the opening token (which is still stored as `peek`).
+        let opening_loc = self.next_token().location();
+        result
+            .ok_or(LatexErrKind::UnclosedGroup(Token::SquareBracketClose))
+            .and_then(|text| text)
+            .map(Some)
+            .map_err(|kind| LatexError(opening_loc, kind))
+    }
+
+    /// Parse the argument of a text-mode command like `\text`.
+    fn parse_text(
+        &mut self,
+        loc: usize,
+        transform: Option<TextTransform>,
+        command: &'static str,
+    ) -> Result<&'arena str, LatexError<'source>> {
+        self.l.text_mode = true;
+        let node = self.parse_single_token()?;
+        let mut builder = self.buffer.get_builder();
+        if !extract_letters(&mut builder, node, transform) {
+            return Err(LatexError(loc, LatexErrKind::ExpectedText(command)));
+        }
+        let text = builder.finish(self.arena);
+        self.l.text_mode = false;
+        // Discard any whitespace tokens that are still stored in self.peek_token.
+        if matches!(self.peek.token(), Token::Whitespace) {
+            self.next_token();
+        }
+        Ok(text)
+    }
+
+    /// Parse the argument of `\tag`, which is text that may contain math like `$n+1$`.
+    ///
+    /// Unless `starred` is set, the tag is put in parentheses.
+    fn parse_tag(
+        &mut self,
+        loc: usize,
+        starred: bool,
+    ) -> Result<NodeList<'arena>, LatexError<'source>> {
+        self.check_lbrace()?;
+        // The mode has to be set before the opening brace is discarded,
+        // because the token after it is read right away.
+        self.l.text_mode = true;
+        self.next_token(); // Discard the opening brace.
+        let mut content = NodeListBuilder::new();
+        // The text since the last piece of math.
+        let mut text = NodeListBuilder::new();
+        if !starred {
+            text.push(self.commit(Node::Text("(")));
+        }
+        loop {
+            match self.peek.token() {
+                Token::GroupEnd => break,
+                Token::Dollar => {
+                    self.l.text_mode = false;
+                    self.next_token(); // Discard the opening dollar sign.
+                    let math = self.parse_group(Token::Dollar)?;
+                    self.l.text_mode = true;
+                    self.next_token(); // Discard the closing dollar sign.
+                    self.finish_tag_text(loc, &mut content, mem::take(&mut text))?;
+                    content.push(self.squeeze(math, None));
+                }
+                _ => {
+                    let next = self.next_token();
+                    if matches!(next.token(), Token::EOF) {
+                        return Err(LatexError(
+                            next.location(),
+                            LatexErrKind::UnclosedGroup(Token::GroupEnd),
+                        ));
+                    }
+                    let node = self.parse_node(next)?;
+                    text.push(node);
+                }
+            }
+        }
+        self.l.text_mode = false;
+        self.next_token(); // Discard the closing brace.
+        if !starred {
+            text.push(self.commit(Node::Text(")")));
+        }
+        self.finish_tag_text(loc, &mut content, text)?;
+        Ok(content.finish())
+    }
+
+    /// Turn the nodes of a piece of text in `\tag` into a single text node.
+    fn finish_tag_text(
+        &mut self,
+        loc: usize,
+        content: &mut NodeListBuilder<'arena>,
+        text: NodeListBuilder<'arena>,
+    ) -> Result<(), LatexError<'source>> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut builder = self.buffer.get_builder();
+        for node in text.finish() {
+            if !extract_text(&mut builder, node.node()) {
+                return Err(LatexError(loc, LatexErrKind::ExpectedText("\\tag")));
+            }
+        }
+        let text = builder.finish(self.arena);
+        content.push(self.commit(Node::Text(text)));
+        Ok(())
+    }
+
+    /// Parse the argument of `\ce`, or of `\pu` if `is_unit` is set.
+    fn parse_chemistry(
+        &mut self,
+        loc: usize,
+        content: &'source str,
+        is_unit: bool,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let items = if is_unit {
+            chem::scan_units(content)
+        } else {
+            chem::scan_formula(content)
+        };
+        let items = items.ok_or(LatexError(loc, LatexErrKind::InvalidChemistry(content)))?;
+        let mut nodes = NodeListBuilder::new();
+        // The last item, which may still get scripts.
+        let mut target: Option<NodeRef<'arena>> = None;
+        let mut sub: Option<NodeRef<'arena>> = None;
+        let mut sup: Option<NodeRef<'arena>> = None;
+        let mut isotope: Option<(Option<NodeRef<'arena>>, Option<NodeRef<'arena>>)> = None;
+        for item in items {
+            let node = match item {
+                ChemItem::Subscript(script) => {
+                    sub = Some(self.parse_chemistry_script(loc, script)?);
+                    continue;
+                }
+                ChemItem::Superscript(script) => {
+                    sup = Some(self.parse_chemistry_script(loc, script)?);
+                    continue;
+                }
+                ChemItem::Isotope(mass, atomic) => {
+                    let mass = mass
+                        .map(|mass| self.parse_chemistry_script(loc, mass))
+                        .transpose()?;
+                    let atomic = atomic
+                        .map(|atomic| self.parse_chemistry_script(loc, atomic))
+                        .transpose()?;
+                    isotope = Some((atomic, mass));
+                    continue;
+                }
+                ChemItem::Math(math) => self.parse_math_fragment(loc, math)?,
+                ChemItem::Letters(letters) => self.commit(upright_letters(letters)),
+                ChemItem::Operator(op) => self.commit(Node::Operator(op, None)),
+                ChemItem::Bond(op) => self.commit(Node::OperatorWithSpacing {
+                    op,
+                    left: Some(MathSpacing::Zero),
+                    right: Some(MathSpacing::Zero),
+                    attr: None,
+                }),
+                ChemItem::Fence(op) => self.commit(Node::Operator(op, Some(OpAttr::StretchyFalse))),
+                ChemItem::Arrow {
+                    arrow,
+                    above,
+                    below,
+                } => {
+                    let arrow = self.chemistry_arrow(arrow);
+                    let above = match above {
+                        Some(above) => self.parse_arrow_label(loc, above)?,
+                        None => self.commit(Node::Row {
+                            nodes: NodeList::empty(),
+                            style: None,
+                        }),
+                    };
+                    let below = below
+                        .map(|below| self.parse_arrow_label(loc, below))
+                        .transpose()?;
+                    self.commit(Node::XArrow {
+                        arrow: arrow.node(),
+                        above: above.node(),
+                        below: below.map(|below| below.node()),
+                    })
+                }
+                ChemItem::Space => self.commit(Node::Space(MathSpacing::ThreeMu.into())),
+            };
+            if let Some(target) = target.take() {
+                let bounds = Bounds(sub.take().map(|s| s.node()), sup.take().map(|s| s.node()));
+                nodes.push(self.attach_bounds(target, bounds));
+            }
+            target = Some(match isotope.take() {
+                Some((atomic, mass)) => {
+                    let mut pre = NodeListBuilder::new();
+                    self.push_script_pair(&mut pre, atomic, mass);
+                    self.commit(Node::Multiscript {
+                        base: node.node(),
+                        pre: pre.finish(),
+                        post: NodeList::empty(),
+                    })
+                }
+                None => node,
+            });
+        }
+        if target.is_some() || sub.is_some() || sup.is_some() {
+            // Scripts without a target are attached to an empty row.
+            let target = target.unwrap_or_else(|| {
+                self.commit(Node::Row {
+                    nodes: NodeList::empty(),
+                    style: None,
+                })
+            });
+            let bounds = Bounds(sub.map(|s| s.node()), sup.map(|s| s.node()));
+            nodes.push(self.attach_bounds(target, bounds));
+        }
+        Ok(self.commit(Node::Row {
+            nodes: nodes.finish(),
+            style: None,
+        }))
+    }
+
+    /// Parse a script in a chemical formula, which may be a charge like `2+`.
+    /// Build a reaction arrow, which stretches to fit its labels.
+    fn chemistry_arrow(&mut self, arrow: ChemArrow) -> NodeRef<'arena> {
+        match arrow {
+            ChemArrow::Single(op) => self.commit(Node::Operator(op, Some(OpAttr::StretchyTrue))),
+            ChemArrow::Unbalanced { favors_products } => {
+                // A long harpoon in the favored direction, with a short one in the other
+                // direction, which is smaller since it is a script.
+                let (long, short) = if favors_products {
+                    (
+                        ops::RIGHTWARDS_HARPOON_WITH_BARB_UPWARDS,
+                        ops::LEFTWARDS_HARPOON_WITH_BARB_DOWNWARDS,
+                    )
+                } else {
+                    (
+                        ops::LEFTWARDS_HARPOON_WITH_BARB_DOWNWARDS,
+                        ops::RIGHTWARDS_HARPOON_WITH_BARB_UPWARDS,
+                    )
+                };
+                let target = self
+                    .commit(Node::Operator(long, Some(OpAttr::StretchyTrue)))
+                    .node();
+                let symbol = self
+                    .commit(Node::Operator(short, Some(OpAttr::StretchyFalse)))
+                    .node();
+                if favors_products {
+                    self.commit(Node::Underset { symbol, target })
+                } else {
+                    self.commit(Node::Overset { symbol, target })
+                }
+            }
+        }
+    }
+
+    /// Parse a label of a reaction arrow.
+    ///
+    /// Like in mhchem, a label in braces is text, and any other label is a formula.
+    fn parse_arrow_label(
+        &mut self,
+        loc: usize,
+        label: &'source str,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let trimmed = label.trim();
+        if let Some(text) = trimmed
+            .strip_prefix('{')
+            .and_then(|text| text.strip_suffix('}'))
+            .filter(|text| !text.contains(['{', '}', '$', '\\']))
+        {
+            Ok(self.commit(Node::Text(text)))
+        } else {
+            self.parse_chemistry(loc, label, false)
+        }
+    }
+
+    fn parse_chemistry_script(
+        &mut self,
+        loc: usize,
+        script: &'source str,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let digits = script.trim_end_matches(['+', '-']);
+        let signs = &script[digits.len()..];
+        if !signs.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            let mut nodes = NodeListBuilder::new();
+            if !digits.is_empty() {
+                nodes.push(self.commit(Node::Number(digits)));
+            }
+            for sign in signs.chars() {
+                let op = if sign == '+' {
+                    ops::PLUS_SIGN
+                } else {
+                    ops::MINUS_SIGN
+                };
+                nodes.push(self.commit(Node::Operator(op, None)));
+            }
+            Ok(self.squeeze(nodes, None))
+        } else if !script.is_empty() && script.chars().all(char::is_alphabetic) {
+            // Oxidation states like `II` are upright.
+            Ok(self.commit(upright_letters(script)))
+        } else {
+            self.parse_math_fragment(loc, script)
+        }
+    }
+
+    /// Consume the star of a starred command like `\abs*`, and return whether there was one.
+    fn parse_star(&mut self) -> bool {
+        if matches!(self.peek.token(), Token::Operator(ops::ASTERISK)) {
+            self.next_token(); // Discard the asterisk.
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether `\qty` is the command of the physics package rather than that of siunitx.
+    ///
+    /// The physics command is followed by a delimiter, while siunitx expects a group,
+    /// which may be preceded by options in brackets, like `\qty[per-mode=symbol]{1}{m}`.
+    fn is_physics_quantity(&mut self) -> bool {
+        match self.peek.token() {
+            Token::Operator(ops::ASTERISK) => true,
+            Token::Paren(
+                ops::LEFT_PARENTHESIS | ops::LEFT_CURLY_BRACKET | ops::VERTICAL_LINE,
+                ..,
+            ) => true,
+            Token::SquareBracketOpen => self.l.peek_bracket_content().map_or(true, |content| {
+                content.is_ok_and(|content| !content.contains('='))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Put delimiters around `content`, which grow with the content if `stretchy` is set.
+    fn fence(
+        &mut self,
+        open: Op,
+        close: Op,
+        content: NodeRef<'arena>,
+        stretchy: bool,
+    ) -> NodeRef<'arena> {
+        if stretchy {
+            return self.commit(Node::Fenced {
+                open,
+                close,
+                content: content.node(),
+                style: None,
+                stretchy: true,
+            });
+        }
+        let mut nodes = NodeListBuilder::new();
+        if open != ops::NULL {
+            nodes.push(self.commit(Node::Operator(open, Some(OpAttr::StretchyFalse))));
+        }
+        nodes.push(content);
+        nodes.push(self.commit(Node::Operator(close, Some(OpAttr::StretchyFalse))));
+        self.commit(Node::Row {
+            nodes: nodes.finish(),
+            style: None,
+        })
+    }
+
+    /// Parse everything up to the delimiter `close`, like the `)` in `\qty(x)`, and
+    /// consume the delimiter.
+    ///
+    /// Like in the physics package, the content ends with the first `close` which is not
+    /// in a group, so `\qty((a)b)` ends after `(a)`. If `bar` is set, a `|` in the content
+    /// is an operator with this attribute.
+    fn parse_delimited(
+        &mut self,
+        close: Op,
+        bar: Option<OpAttr>,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let mut nodes = NodeListBuilder::new();
+        loop {
+            let next = self.next_token();
+            let node = match next.token() {
+                Token::Paren(op, ..) if *op == close => break,
+                Token::SquareBracketClose if close == ops::RIGHT_SQUARE_BRACKET => break,
+                Token::EOF => {
+                    return Err(LatexError(
+                        next.location(),
+                        LatexErrKind::UnclosedGroup(Token::Paren(close, None, Stretchy::Always)),
+                    ));
+                }
+                Token::Paren(ops::VERTICAL_LINE, ..) if bar.is_some() => {
+                    self.commit(Node::Operator(ops::VERTICAL_LINE, bar))
+                }
+                _ if nodes.is_empty() => self.parse_first_node(next)?,
+                _ => self.parse_node(next)?,
+            };
+            nodes.push(node);
+        }
+        Ok(self.squeeze(nodes, None))
+    }
+
+    /// Parse the rest of a group after the opening brace, where a `|` is an operator
+    /// with the attribute `bar`, like in `\braket{a|b}`.
+    fn parse_delimited_group(
+        &mut self,
+        bar: Option<OpAttr>,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let mut nodes = NodeListBuilder::new();
+        loop {
+            let next = self.next_token();
+            let node = match next.token() {
+                Token::GroupEnd => break,
+                Token::EOF => {
+                    return Err(LatexError(
+                        next.location(),
+                        LatexErrKind::UnclosedGroup(Token::GroupEnd),
+                    ));
+                }
+                Token::Paren(ops::VERTICAL_LINE, ..) => {
+                    self.commit(Node::Operator(ops::VERTICAL_LINE, bar))
+                }
+                _ if nodes.is_empty() => self.parse_first_node(next)?,
+                _ => self.parse_node(next)?,
+            };
+            nodes.push(node);
+        }
+        Ok(self.squeeze(nodes, None))
+    }
+
+    /// Create the differential `d` or `∂` of a derivative, with the order as a superscript.
+    fn differential(
+        &mut self,
+        loc: usize,
+        d: char,
+        order: Option<&'source str>,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let variant = (d == 'd').then_some(MathVariant::Normal);
+        let d = self.commit(Node::SingleLetterIdent(d, variant));
+        let Some(order) = order else {
+            return Ok(d);
+        };
+        let symbol = self.parse_math_fragment(loc, order)?.node();
+        Ok(self.commit(Node::Superscript {
+            target: d.node(),
+            symbol,
+        }))
     }
 
-    /// Parse the contents of a group which can contain any expression.
-    fn parse_group(
+    /// Parse the optional argument of a command like `\qty`, which overrides the options
+    /// of `\sisetup` for this command only.
+    fn parse_unit_options(&mut self, loc: usize) -> Result<UnitOptions, LatexError<'source>> {
+        let mut options = self.unit_options;
+        if let Some(input) = self.parse_optional_text_group()? {
+            options
+                .apply(input)
+                .map_err(|option| LatexError(loc, LatexErrKind::InvalidUnitOption(option)))?;
+        }
+        Ok(options)
+    }
+
+    /// Push the nodes of a number like `1.23(4)e-5`.
+    fn push_number(
         &mut self,
-        end_token: Token<'source>,
-    ) -> Result<NodeListBuilder<'arena>, LatexError<'source>> {
-        let mut nodes = NodeListBuilder::new();
+        nodes: &mut NodeListBuilder<'arena>,
+        number: &Number<'source>,
+        options: &UnitOptions,
+    ) {
+        // A separate uncertainty is put in parentheses if there is an exponent,
+        // like in `(1.2 ± 0.1) × 10^3`.
+        let parenthesized = matches!(number.uncertainty, Some(Uncertainty::Separate(_)))
+            && number.exponent.is_some();
+        let paren = |op| Node::Operator(op, Some(OpAttr::StretchyFalse));
+        if parenthesized {
+            nodes.push(self.commit(paren(ops::LEFT_PARENTHESIS)));
+        }
+        if let Some(sign) = number.sign {
+            nodes.push(self.commit(Node::Operator(sign, None)));
+        }
+        if let Some(mantissa) = number.mantissa {
+            nodes.push(self.digits_node(&mantissa, options));
+        }
+        match number.uncertainty {
+            Some(Uncertainty::Compact(digits)) => {
+                nodes.push(self.commit(paren(ops::LEFT_PARENTHESIS)));
+                nodes.push(self.digits_node(&digits, options));
+                nodes.push(self.commit(paren(ops::RIGHT_PARENTHESIS)));
+            }
+            Some(Uncertainty::Separate(digits)) => {
+                nodes.push(self.commit(Node::Operator(ops::PLUS_MINUS_SIGN, None)));
+                nodes.push(self.digits_node(&digits, options));
+            }
+            None => {}
+        }
+        if parenthesized {
+            nodes.push(self.commit(paren(ops::RIGHT_PARENTHESIS)));
+        }
+        if let Some((sign, exponent)) = number.exponent {
+            if number.mantissa.is_some() {
+                nodes.push(self.commit(Node::Operator(options.exponent_product, None)));
+            }
+            let mut symbol = NodeListBuilder::new();
+            if let Some(sign) = sign {
+                symbol.push(self.commit(Node::Operator(sign, None)));
+            }
+            symbol.push(self.commit(Node::Number(exponent)));
+            let symbol = self.squeeze(symbol, None).node();
+            let target = self.commit(Node::Number("10")).node();
+            nodes.push(self.commit(Node::Superscript { target, symbol }));
+        }
+    }
 
-        while !self.peek.token().is_same_kind(&end_token) {
-            let next = self.next_token();
-            if matches!(next.token(), Token::EOF) {
-                // When the input ends without the closing token.
-                return Err(LatexError(
-                    next.location(),
-                    LatexErrKind::UnclosedGroup(end_token),
-                ));
+    fn digits_node(&mut self, digits: &Digits, options: &UnitOptions) -> NodeRef<'arena> {
+        let mut builder = self.buffer.get_builder();
+        digits.write(options, &mut builder);
+        let number = builder.finish(self.arena);
+        self.commit(Node::Number(number))
+    }
+
+    /// Push the nodes of a list of units, where the units after `\per` are shown
+    /// according to the `per-mode` option.
+    fn push_units(
+        &mut self,
+        nodes: &mut NodeListBuilder<'arena>,
+        units: &[Unit<'source>],
+        options: &UnitOptions,
+    ) {
+        let (denominator, numerator): (Vec<_>, Vec<_>) = units.iter().partition(|unit| unit.per);
+        if options.per_mode == PerMode::Power || denominator.is_empty() {
+            for (i, unit) in units.iter().enumerate() {
+                if i > 0 {
+                    nodes.push(self.commit(Node::Space(MathSpacing::ThreeMu.into())));
+                }
+                nodes.push(self.unit_node(unit, unit.per));
+            }
+            return;
+        }
+        let mut num = NodeListBuilder::new();
+        for (i, unit) in numerator.iter().enumerate() {
+            if i > 0 {
+                num.push(self.commit(Node::Space(MathSpacing::ThreeMu.into())));
+            }
+            num.push(self.unit_node(unit, false));
+        }
+        if num.is_empty() {
+            num.push(self.commit(Node::Number("1")));
+        }
+        let mut den = NodeListBuilder::new();
+        for (i, unit) in denominator.iter().enumerate() {
+            if i > 0 {
+                den.push(self.commit(Node::Space(MathSpacing::ThreeMu.into())));
             }
+            den.push(self.unit_node(unit, false));
+        }
+        if options.per_mode == PerMode::Fraction {
+            let num = self.squeeze(num, None).node();
+            let den = self.squeeze(den, None).node();
+            nodes.push(self.commit(Node::Frac {
+                num,
+                den,
+                lt: None,
+                attr: None,
+            }));
+        } else {
+            nodes.push(self.squeeze(num, None));
+            nodes.push(self.commit(Node::Operator(ops::SOLIDUS, None)));
+            if denominator.len() > 1 {
+                let content = self.squeeze(den, None).node();
+                nodes.push(self.commit(Node::Fenced {
+                    open: ops::LEFT_PARENTHESIS,
+                    close: ops::RIGHT_PARENTHESIS,
+                    content,
+                    style: None,
+                    stretchy: false,
+                }));
+            } else {
+                nodes.push(self.squeeze(den, None));
+            }
+        }
+    }
+
+    /// Create the node of a single unit, with a negative power if `negate` is set.
+    fn unit_node(&mut self, unit: &Unit<'source>, negate: bool) -> NodeRef<'arena> {
+        let symbol = if unit.prefix.is_empty() {
+            unit.symbol
+        } else {
+            let mut builder = self.buffer.get_builder();
+            builder.push_str(unit.prefix);
+            builder.push_str(unit.symbol);
+            builder.finish(self.arena)
+        };
+        let base = self.commit(upright_letters(symbol));
+        if unit.power.is_none() && !negate {
+            return base;
+        }
+        let power = unit.power.unwrap_or("1");
+        let (negative, power) = match power.strip_prefix('-') {
+            Some(power) => (!negate, power),
+            None => (negate, power),
+        };
+        let mut symbol = NodeListBuilder::new();
+        if negative {
+            symbol.push(self.commit(Node::Operator(ops::MINUS_SIGN, None)));
+        }
+        symbol.push(self.commit(Node::Number(power)));
+        let symbol = self.squeeze(symbol, None).node();
+        self.commit(Node::Superscript {
+            target: base.node(),
+            symbol,
+        })
+    }
+
+    /// Parse a piece of LaTeX code on its own, like the math parts of `\ce`.
+    ///
+    /// Errors are reported at `loc`, because the positions within the fragment are not
+    /// positions in the input.
+    fn parse_math_fragment(
+        &mut self,
+        loc: usize,
+        fragment: &'source str,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        // The fragment is read by this parser, so that it knows the macros, the colors and
+        // the options. The token after the fragment has already been read, so it is kept.
+        self.l.push_fragment(fragment, loc)?;
+        let next = self.l.next_token(false);
+        let peek = mem::replace(&mut self.peek, next);
+        let content = self.parse_group(Token::EOF);
+        self.l.pop_fragment();
+        self.peek = peek;
+        Ok(self.squeeze(content?, None))
+    }
+
+    /// Parse everything up to the end of the enclosing group, table cell or formula.
+    ///
+    /// This is used for switches like `\color`, which affect everything after them.
+    fn parse_rest_of_scope(&mut self) -> Result<NodeList<'arena>, LatexError<'source>> {
+        let mut nodes = NodeListBuilder::new();
+        while !matches!(
+            self.peek.token(),
+            Token::GroupEnd
+                | Token::End
+                | Token::Right
+                | Token::Ampersand
+                | Token::NewLine
+                | Token::EOF
+        ) {
+            let next = self.next_token();
             let node = self.parse_node(next)?;
             nodes.push(node);
         }
-        Ok(nodes)
+        Ok(nodes.finish())
     }
 
-    /// Parse the contents of a group which can only contain text.
-    fn parse_text_group(&mut self) -> Result<&'source str, LatexError<'source>> {
-        let result = self.l.read_text_content();
-        // Discard the opening token (which is still stored as `peek`).
-        let opening_loc = self.next_token().location();
-        result.ok_or(LatexError(
-            opening_loc,
-            LatexErrKind::UnclosedGroup(Token::GroupEnd),
-        ))
+    /// Add the automatic equation numbers to the rows of a display environment.
+    ///
+    /// If `single` is set, the whole environment gets only one number, on the last row.
+    /// Returns the new content, and the side of the tags if any row has a tag.
+    fn number_rows(
+        &mut self,
+        content: NodeList<'arena>,
+        numbered: bool,
+        single: bool,
+    ) -> (NodeList<'arena>, Option<TagPosition>) {
+        let mut list_builder = NodeListBuilder::new();
+        let mut has_tags = false;
+        // Whether the current row has `\tag` or `\notag`.
+        let mut tagged = false;
+        let mut label: Option<&str> = None;
+        let mut nodes = content.into_iter();
+        loop {
+            let node_ref = nodes.next();
+            let row_end = match &node_ref {
+                Some(node_ref) => matches!(node_ref.node(), Node::RowSeparator),
+                None => true,
+            };
+            if row_end && (!single || node_ref.is_none()) {
+                let number = match self.equation_counter.as_mut() {
+                    Some(counter) if numbered && !tagged => {
+                        *counter += 1;
+                        Some(*counter)
+                    }
+                    _ => None,
+                };
+                if let Some(number) = number {
+                    let mut builder = self.buffer.get_builder();
+                    builder.push_char('(');
+                    builder.push_str(&number.to_string());
+                    builder.push_char(')');
+                    let tag = builder.finish(self.arena);
+                    let mut content = NodeListBuilder::new();
+                    content.push(self.commit(Node::Text(tag)));
+                    list_builder.push(self.commit(Node::Tag(content.finish())));
+                    self.equation_numbers.push(EquationNumber {
+                        number,
+                        label: label.map(str::to_string),
+                    });
+                    has_tags = true;
+                }
+                tagged = false;
+                label = None;
+            }
+            let Some(node_ref) = node_ref else {
+                break;
+            };
+            match node_ref.node() {
+                Node::Tag(_) => {
+                    tagged = true;
+                    has_tags = true;
+                }
+                Node::NoTag => tagged = true,
+                Node::Label(key) => label = Some(key),
+                _ => {}
+            }
+            list_builder.push(node_ref);
+        }
+        (list_builder.finish(), has_tags.then_some(self.tag_position))
+    }
+
+    /// Give the content the spacing of the given atom class, like `\mathrel{...}`.
+    fn with_class(&mut self, content: NodeRef<'arena>, class: MathClass) -> Node<'arena> {
+        let (left, right) = class.spacing();
+        match content.node() {
+            // Letters are turned into operators, unless they stay ordinary.
+            Node::SingleLetterIdent(c, _) if !matches!(class, MathClass::Ord) => {
+                return Node::OperatorWithSpacing {
+                    op: Op::from_char(*c),
+                    left: Some(left),
+                    right: Some(right),
+                    attr: None,
+                };
+            }
+            node => {
+                if let Some(node) = self.respace_operator(node, left, right) {
+                    return node;
+                }
+            }
+        }
+        // Otherwise, the spaces are put around the content.
+        let mut builder = NodeListBuilder::new();
+        if left != MathSpacing::Zero {
+            builder.push(self.commit(Node::Space(left.into())));
+        }
+        let nodes = match content.mut_node() {
+            Node::Row { nodes, style: None } => Some(mem::replace(nodes, NodeList::empty())),
+            _ => None,
+        };
+        match nodes {
+            Some(nodes) => {
+                for node_ref in nodes {
+                    builder.push(node_ref);
+                }
+            }
+            None => builder.push(content),
+        }
+        if right != MathSpacing::Zero {
+            builder.push(self.commit(Node::Space(right.into())));
+        }
+        Node::Row {
+            nodes: builder.finish(),
+            style: None,
+        }
+    }
+
+    /// If the node is an operator, possibly with scripts or accents, return a copy of it
+    /// where the operator has the given spacing.
+    ///
+    /// In MathML, the spacing of such an embellished operator is taken from its core.
+    fn respace_operator(
+        &mut self,
+        node: &Node<'arena>,
+        left: MathSpacing,
+        right: MathSpacing,
+    ) -> Option<Node<'arena>> {
+        let mut respace = |target: &Node<'arena>| -> Option<&'arena Node<'arena>> {
+            let node = self.respace_operator(target, left, right)?;
+            Some(self.commit(node).node())
+        };
+        Some(match node {
+            Node::Operator(op, _) | Node::OperatorWithSpacing { op, .. } => {
+                Node::OperatorWithSpacing {
+                    op: *op,
+                    left: Some(left),
+                    right: Some(right),
+                    attr: None,
+                }
+            }
+            Node::Subscript { target, symbol } => Node::Subscript {
+                target: respace(target)?,
+                symbol,
+            },
+            Node::Superscript { target, symbol } => Node::Superscript {
+                target: respace(target)?,
+                symbol,
+            },
+            Node::SubSup { target, sub, sup } => Node::SubSup {
+                target: respace(target)?,
+                sub,
+                sup,
+            },
+            Node::OverOp(op, accent, attr, target) => {
+                Node::OverOp(*op, *accent, *attr, respace(target)?)
+            }
+            Node::UnderOp(op, accent, target) => Node::UnderOp(*op, *accent, respace(target)?),
+            Node::Overset { symbol, target } => Node::Overset {
+                symbol,
+                target: respace(target)?,
+            },
+            Node::Underset { symbol, target } => Node::Underset {
+                symbol,
+                target: respace(target)?,
+            },
+            Node::UnderOver {
+                target,
+                under,
+                over,
+            } => Node::UnderOver {
+                target: respace(target)?,
+                under,
+                over,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Convert a dimension like `3pt` to a CSS length.
+    fn convert_dimension(
+        &mut self,
+        loc: usize,
+        dimension: &'source str,
+    ) -> Result<&'arena str, LatexError<'source>> {
+        let Some(length) = dimension::parse_dimension(dimension) else {
+            return Err(LatexError(loc, LatexErrKind::InvalidDimension(dimension)));
+        };
+        Ok(self.css_length(length))
+    }
+
+    /// Write a converted dimension as a CSS length into the arena.
+    fn css_length(&mut self, length: (f32, &str)) -> &'arena str {
+        let mut builder = self.buffer.get_builder();
+        push_length(&mut builder, length);
+        builder.finish(self.arena)
+    }
+
+    /// Parse the optional vertical position of an environment like `aligned`.
+    ///
+    /// Only `[c]` is accepted, because MathML Core always centers a table on the math
+    /// axis, so `[t]` and `[b]` can't be honoured.
+    fn parse_position(&mut self) -> Result<(), LatexError<'source>> {
+        let loc = self.peek.location();
+        match self.parse_optional_text_group()?.map(str::trim) {
+            None | Some("c") => Ok(()),
+            Some(option) => Err(LatexError(loc, LatexErrKind::InvalidPosition(option))),
+        }
+    }
+
+    /// Parse the column specification of an `array`, like `{r|c@{\,}l}`.
+    ///
+    /// The result is a list of `Node::ArrayColumn`, one for each column and one
+    /// for each piece of material from `@{...}` and `!{...}`.
+    fn parse_column_spec(
+        &mut self,
+        env_name: &'source str,
+    ) -> Result<NodeList<'arena>, LatexError<'source>> {
+        self.check_lbrace()?;
+        self.next_token(); // Discard the opening token.
+        let mut columns: Vec<ArrayColumn> = Vec::new();
+        // A rule before the first column.
+        let mut left_rule: Option<LineStyle> = None;
+        // Whether the previous material was `@{...}`, which suppresses the padding.
+        let mut suppress_padding = false;
+        loop {
+            let TokLoc(loc, token) = self.next_token();
+            let rule = match token {
+                Token::GroupEnd => break,
+                Token::Paren(ops::VERTICAL_LINE, _, _) => Some(LineStyle::Solid),
+                Token::Colon => Some(LineStyle::Dashed),
+                _ => None,
+            };
+            if let Some(rule) = rule {
+                match columns.last_mut() {
+                    Some(column) => column.right = add_rule(column.right, rule),
+                    None => left_rule = add_rule(left_rule, rule),
+                }
+                continue;
+            }
+            let mut column = ArrayColumn {
+                align: "",
+                width: None,
+                material: None,
+                left: left_rule.take(),
+                right: None,
+                padding_left: !mem::replace(&mut suppress_padding, false),
+                padding_right: true,
+            };
+            match token {
+                Token::Letter('l') => column.align = ALIGN_LEFT,
+                Token::Letter('c') => {}
+                Token::Letter('r') => column.align = ALIGN_RIGHT,
+                Token::Letter('p') => {
+                    self.check_lbrace()?;
+                    let width = self.parse_text_group()?;
+                    if !is_valid_width(width) {
+                        return Err(LatexError(loc, LatexErrKind::InvalidColumnSpec(env_name)));
+                    }
+                    column.align = ALIGN_LEFT;
+                    column.width = Some(width);
+                }
+                Token::Letter('@') | Token::Operator(ops::EXCLAMATION_MARK) => {
+                    let is_at = matches!(token, Token::Letter('@'));
+                    self.check_lbrace()?;
+                    let material = self.parse_token()?;
+                    if is_at {
+                        if let Some(prev) = columns.last_mut() {
+                            prev.padding_right = false;
+                        }
+                        suppress_padding = true;
+                    }
+                    if matches!(material, Node::Row { nodes, .. } if nodes.is_empty()) {
+                        left_rule = column.left;
+                        continue;
+                    }
+                    column.material = Some(material);
+                }
+                _ => return Err(LatexError(loc, LatexErrKind::InvalidColumnSpec(env_name))),
+            }
+            columns.push(column);
+        }
+        if !columns.iter().any(|column| column.material.is_none()) {
+            let loc = self.peek.location();
+            return Err(LatexError(loc, LatexErrKind::InvalidColumnSpec(env_name)));
+        }
+
+        let mut list_builder = NodeListBuilder::new();
+        for column in columns {
+            let mut builder = self.buffer.get_builder();
+            let mut separator = "";
+            let mut push = |property: &str, value: &str| {
+                builder.push_str(separator);
+                builder.push_str(property);
+                builder.push_str(value);
+                separator = "; ";
+            };
+            if !column.align.is_empty() {
+                push("", column.align);
+            }
+            if let Some(width) = column.width {
+                push("width: ", width.trim());
+            }
+            if let Some(rule) = column.left {
+                push("border-left: ", rule.as_ref());
+            }
+            if let Some(rule) = column.right {
+                push("border-right: ", rule.as_ref());
+            }
+            if column.material.is_some() {
+                push("padding: 0", "");
+            } else {
+                if !column.padding_left {
+                    push("padding-left: 0", "");
+                }
+                if !column.padding_right {
+                    push("padding-right: 0", "");
+                }
+            }
+            let style = builder.finish(self.arena);
+            list_builder.push(self.commit(Node::ArrayColumn {
+                style,
+                material: column.material,
+            }));
+        }
+        Ok(list_builder.finish())
+    }
+
+    /// Parse a color argument like `{red!50}` or `[rgb]{1,0,0}`.
+    fn parse_color(&mut self) -> Result<Color, LatexError<'source>> {
+        let model = self.parse_optional_text_group()?;
+        self.check_lbrace()?;
+        let loc = self.peek.location();
+        let spec = self.parse_text_group()?;
+        let color = match model {
+            Some(model) => color::parse_color_model(model, spec),
+            None => get_color(&self.colors, spec),
+        };
+        color.ok_or(LatexError(loc, LatexErrKind::UnknownColor(spec)))
+    }
+
+    /// Parse a single node and return its content as a list.
+    /// If the node is a group, the list contains the elements of the group.
+    fn parse_single_node_as_list(&mut self) -> Result<NodeList<'arena>, LatexError<'source>> {
+        let token = self.next_token();
+        let node_ref = self.parse_single_node(token)?;
+        if let Node::Row { nodes, style: None } = node_ref.mut_node() {
+            return Ok(mem::replace(nodes, NodeList::empty()));
+        }
+        let mut builder = NodeListBuilder::new();
+        builder.push(node_ref);
+        Ok(builder.finish())
     }
 
     fn check_lbrace(&mut self) -> Result<(), LatexError<'source>> {
@@ -767,9 +2868,72 @@ where
         Ok(())
     }
 
+    /// Parse the bounds of an operator, which may be preceded by `\limits`, `\nolimits`
+    /// or `\displaylimits` to override the default placement.
+    ///
+    /// The operator itself is created by `make_target`, which is told where the limits
+    /// go if they are placed below and above, or if they were given explicitly.
+    fn parse_limits(
+        &mut self,
+        default: Limits,
+        make_target: impl FnOnce(Option<Limits>) -> Node<'arena>,
+    ) -> Result<NodeRef<'arena>, LatexError<'source>> {
+        let mut limits = default;
+        let mut explicit = false;
+        // Like in LaTeX, the last of several limits commands wins.
+        while let Token::Limits(l) = self.peek.token() {
+            limits = *l;
+            explicit = true;
+            self.next_token(); // Discard the limits token.
+        }
+        let bounds = self.get_bounds()?;
+        let under_over = !matches!(limits, Limits::Never) && !matches!(bounds, Bounds(None, None));
+        let target = self.commit(make_target((under_over || explicit).then_some(limits)));
+        let node = match bounds {
+            Bounds(None, None) => return Ok(target),
+            Bounds(Some(under), Some(over)) if under_over => Node::UnderOver {
+                target: target.node(),
+                under,
+                over,
+            },
+            Bounds(Some(symbol), None) if under_over => Node::Underset {
+                target: target.node(),
+                symbol,
+            },
+            Bounds(None, Some(symbol)) if under_over => Node::Overset {
+                target: target.node(),
+                symbol,
+            },
+            Bounds(Some(sub), Some(sup)) => Node::SubSup {
+                target: target.node(),
+                sub,
+                sup,
+            },
+            Bounds(Some(symbol), None) => Node::Subscript {
+                target: target.node(),
+                symbol,
+            },
+            Bounds(None, Some(symbol)) => Node::Superscript {
+                target: target.node(),
+                symbol,
+            },
+        };
+        Ok(self.commit(node))
+    }
+
     /// Parse the bounds of an integral, sum, or product.
     /// These bounds are preceeded by `_` or `^`.
     fn get_bounds(&mut self) -> Result<Bounds<'arena>, LatexError<'source>> {
+        let (sub, sup) = self.get_script_refs()?;
+        Ok(Bounds(sub.map(|s| s.node()), sup.map(|s| s.node())))
+    }
+
+    /// Parse a subscript and a superscript, like `get_bounds`, but return references
+    /// which can still be added to a list.
+    #[allow(clippy::type_complexity)]
+    fn get_script_refs(
+        &mut self,
+    ) -> Result<(Option<NodeRef<'arena>>, Option<NodeRef<'arena>>), LatexError<'source>> {
         let mut primes = self.prime_check();
         // Check whether the first bound is specified and is a lower bound.
         let first_underscore = matches!(self.peek.token(), Token::Underscore);
@@ -826,7 +2990,7 @@ where
             sup
         };
 
-        Ok(Bounds(sub.map(|s| s.node()), sup.map(|s| s.node())))
+        Ok((sub, sup))
     }
 
     fn prime_check(&mut self) -> NodeListBuilder<'arena> {
@@ -944,6 +3108,77 @@ where
     }
 }
 
+/// The padding of `\fbox` and friends, which corresponds to `\fboxsep`.
+const FBOX_PADDING: &str = "padding: 3pt";
+/// The padding and the frame of `\fbox`; the width of the frame corresponds to `\fboxrule`.
+const FBOX_STYLE: &str = "padding: 3pt; border: 0.4pt solid";
+
+/// Parse a length in an option of `\bbox`, which may also be given in pixels like in MathJax.
+fn parse_bbox_length(input: &str) -> Option<(f32, &'static str)> {
+    match input.strip_suffix("px") {
+        Some(pixels) => pixels
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|pixels| pixels.is_finite())
+            .map(|pixels| (pixels, "px")),
+        None => dimension::parse_dimension(input),
+    }
+}
+
+/// Write a converted dimension as a CSS length.
+fn push_length(builder: &mut StringBuilder, (value, unit): (f32, &str)) {
+    // Four decimal places are more than enough.
+    let value = (value * 10000.0).round() / 10000.0;
+    // Avoid printing `-0`.
+    builder.push_str(&if value == 0.0 { 0.0 } else { value }.to_string());
+    builder.push_str(unit);
+}
+
+/// Look up a color expression like `red!50`, taking colors defined with `\definecolor` into account.
+fn get_color(colors: &[(&str, Color)], spec: &str) -> Option<Color> {
+    color::parse_color_expression(spec, |name| {
+        match colors.iter().rev().find(|(n, _)| *n == name) {
+            Some((_, color)) => Some(*color),
+            None => color::get_named_color(name),
+        }
+    })
+}
+
+const ALIGN_LEFT: &str = "text-align: -webkit-left; text-align: -moz-left";
+const ALIGN_RIGHT: &str = "text-align: -webkit-right; text-align: -moz-right";
+
+/// A column of an `array`, while the column specification is being parsed.
+struct ArrayColumn<'arena, 'source> {
+    align: &'static str,
+    width: Option<&'source str>,
+    /// The content of `@{...}` or `!{...}`, if this is not a real column.
+    material: Option<&'arena Node<'arena>>,
+    left: Option<LineStyle>,
+    right: Option<LineStyle>,
+    padding_left: bool,
+    padding_right: bool,
+}
+
+/// Add a vertical rule next to a column; two rules make a double rule.
+fn add_rule(existing: Option<LineStyle>, rule: LineStyle) -> Option<LineStyle> {
+    match existing {
+        None => Some(rule),
+        Some(_) => Some(LineStyle::Double),
+    }
+}
+
+/// Check that the width of a `p{...}` column is a dimension which CSS understands.
+fn is_valid_width(width: &str) -> bool {
+    let width = width.trim();
+    let split = width
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(width.len());
+    let (number, unit) = width.split_at(split);
+    number.parse::<f32>().is_ok_and(|n| n >= 0.0)
+        && matches!(unit, "em" | "ex" | "pt" | "pc" | "cm" | "mm" | "in" | "px")
+}
+
 struct Bounds<'arena>(Option<&'arena Node<'arena>>, Option<&'arena Node<'arena>>);
 
 struct LetterCollector<'arena, 'buffer> {
@@ -962,6 +3197,54 @@ impl<'arena> LetterCollector<'arena, '_> {
     }
 }
 
+/// Check the argument of `\htmlData`, which is a list like `key=value, other=value`.
+///
+/// Every key becomes the name of a `data-*` attribute, so it may only contain ASCII
+/// letters, digits, `-` and `_`. The values can be anything, because they are escaped.
+fn is_valid_html_data(data: &str) -> bool {
+    data.split(',').all(|item| {
+        item.split_once('=').is_some_and(|(key, _)| {
+            let key = key.trim();
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        })
+    })
+}
+
+/// Create the node for upright letters, like an element symbol.
+fn upright_letters(letters: &str) -> Node<'_> {
+    let mut chars = letters.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Node::SingleLetterIdent(c, Some(MathVariant::Normal)),
+        _ => Node::MultiLetterIdent(letters),
+    }
+}
+
+/// The `movablelimits` attribute of an operator whose limits are placed as given.
+///
+/// Limits below and above only move to the side outside of display style if the
+/// attribute is set, because most operators don't have limits by default.
+fn movable_limits(limits: Option<Limits>) -> Option<OpAttr> {
+    match limits {
+        Some(Limits::Display) => Some(OpAttr::MovableLimits),
+        Some(Limits::Always) => Some(OpAttr::NoMovableLimits),
+        Some(Limits::Never) | None => None,
+    }
+}
+
+/// Create the node for a multi-letter operator like `\lim` or `\operatorname`.
+///
+/// Operators with limits are an `<mo>`, however the limits are placed; the others are
+/// identifiers like `\sin`.
+fn operator_name(name: &str, limits: Option<Limits>) -> Node<'_> {
+    match limits {
+        Some(_) => Node::OperatorName(name, movable_limits(limits)),
+        None => Node::MultiLetterIdent(name),
+    }
+}
+
 /// Extract the text of all single-letter identifiers and operators in `node`.
 /// This function cannot be a method, because we need to borrow arena immutably
 /// but buffer mutably. This is not possible with a mutable self reference.
@@ -1000,16 +3283,38 @@ fn extract_letters<'arena>(
         Node::Text(str_ref) => {
             buffer.push_str(str_ref);
         }
+        // Spaces like in `\operatorname{arg\,max}` can't be part of an `<mi>`,
+        // so they are approximated with a thin space character.
+        Node::Space(_) => {
+            buffer.push_char('\u{2009}');
+        }
         _ => return false,
     }
     true
 }
 
+/// Like `extract_letters`, but the characters which need escaping are allowed as well.
+///
+/// The resulting text has to be escaped when it is emitted.
+fn extract_text<'arena>(buffer: &mut StringBuilder, node: &'arena Node<'arena>) -> bool {
+    match node {
+        Node::OpAmpersand => buffer.push_char('&'),
+        Node::OpLessThan => buffer.push_char('<'),
+        Node::OpGreaterThan => buffer.push_char('>'),
+        Node::Row { nodes, .. } | Node::PseudoRow(nodes) => {
+            return nodes.iter().all(|node| extract_text(buffer, node));
+        }
+        node => return extract_letters(buffer, node, None),
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_ron_snapshot;
 
     use super::*;
+    use crate::lexer::Lexer;
 
     #[test]
     fn ast_test() {
@@ -1025,7 +3330,7 @@ mod tests {
         for (name, problem) in problems.into_iter() {
             let arena = Arena::new();
             let l = Lexer::new(problem);
-            let mut p = Parser::new(l, &arena);
+            let mut p = Parser::new(MacroExpander::new(l, None), &arena);
             let ast = p.parse().expect("Parsing failed");
             if let Node::PseudoRow(nodes) = ast {
                 assert_ron_snapshot!(name, &nodes, problem);
@@ -2,7 +2,10 @@ use std::mem::discriminant;
 
 use strum_macros::AsRefStr;
 
-use crate::attribute::{FracAttr, MathVariant, OpAttr, ParenAttr, Stretchy, Style, TextTransform};
+use crate::attribute::{
+    FracAttr, HtmlAttr, Limits, LineStyle, MathClass, MathVariant, Notation, OpAttr, Padding,
+    ParenAttr, Stretchy, Style, TextTransform,
+};
 use crate::ops::Op;
 
 #[derive(Debug, Clone, Copy, PartialEq, AsRefStr)]
@@ -51,19 +54,47 @@ pub enum Token<'source> {
     Overset,
     #[strum(serialize = r"\underset")]
     Underset,
+    #[strum(serialize = r"\overunderset")]
+    OverUnderset,
+    #[strum(serialize = r"\underoverset")]
+    UnderOverset,
+    /// `\stackrel` and `\stackbin`, which stack a symbol and give it the class.
+    Stackrel(MathClass),
     OverUnderBrace(Op, bool),
     #[strum(serialize = r"\sqrt")]
     Sqrt,
     Integral(Op),
     #[strum(serialize = r"\limits")]
-    Limits,
+    Limits(Limits),
     Lim(&'static str),
     Space(&'static str),
+    #[strum(serialize = r"\kern")]
+    Kern,
+    #[strum(serialize = r"\hskip")]
+    Skip,
+    #[strum(serialize = r"\mkern")]
+    MathKern,
+    #[strum(serialize = r"\mskip")]
+    MathSkip,
+    /// The raw dimension of a spacing command like `\kern`.
+    Dimension(&'source str),
+    /// The raw dimension of `\mkern`, `\mskip` or `\mspace`, which has to be in `mu`.
+    MathDimension(&'source str),
+    /// `\char`, which is followed by a TeX number like `"2A01`.
+    #[strum(serialize = r"\char")]
+    Char,
+    /// `\unicode` or `\symbol`, which are followed by a character code in braces.
+    #[strum(serialize = r"\unicode")]
+    Unicode,
+    /// A raw character code which doesn't denote a valid character.
+    InvalidCharCode(&'source str),
     #[strum(serialize = "~")]
     NonBreakingSpace,
     Whitespace,
     SingleSpace,
     Transform(Option<TextTransform>, Option<MathVariant>),
+    /// Old font declarations like `\rm` and `\bf`, which affect the rest of the scope.
+    FontSwitch(Option<TextTransform>, Option<MathVariant>),
     Big(&'static str),
     OverUnder(Op, bool, Option<OpAttr>),
     Operator(Op),
@@ -77,6 +108,10 @@ pub enum Token<'source> {
     OpAmpersand,
     #[strum(serialize = ":")]
     Colon,
+    /// A dollar sign, which starts and ends math within the text of `\tag`.
+    /// An escaped dollar sign, `\$`, is a letter instead.
+    #[strum(serialize = "$")]
+    Dollar,
     BigOp(Op),
     Letter(char),
     UprightLetter(char), // letter for which we need `mathvariant="normal"`
@@ -85,15 +120,104 @@ pub enum Token<'source> {
     NumberWithComma(&'source str),
     Function(&'static str),
     #[strum(serialize = r"\operatorname")]
-    OperatorName,
+    OperatorName(bool),
+    #[strum(serialize = r"\DeclareMathOperator")]
+    DeclareMathOperator,
     Slashed,
+    Sideset,
+    Prescript,
     #[strum(serialize = r"\not")]
     Not,
     #[strum(serialize = r"\text*")]
     Text(Option<TextTransform>),
     #[strum(serialize = r"\mathstrut")]
     Mathstrut,
+    /// `\ce` for chemical formulas, or `\pu` for physical units if the flag is set.
+    #[strum(serialize = r"\ce")]
+    Chemistry(bool),
+    /// `\abs` or `\norm`, with automatically sized delimiters.
+    Delimited(Op, Op),
+    /// `\bra`, `\ket` or `\braket`, in which `|` is sized like the delimiters.
+    BraKet(Op, Op),
+    /// `\dv`, or `\pdv` for partial derivatives if the flag is set.
+    #[strum(serialize = r"\dv")]
+    Derivative(bool),
+    #[strum(serialize = r"\eval")]
+    Evaluated,
+    #[strum(serialize = r"\order")]
+    Order,
+    #[strum(serialize = r"\vb")]
+    VectorBold,
+    #[strum(serialize = r"\num")]
+    Num,
+    #[strum(serialize = r"\unit")]
+    Unit,
+    #[strum(serialize = r"\qty")]
+    Quantity,
+    #[strum(serialize = r"\ang")]
+    Angle,
+    #[strum(serialize = r"\sisetup")]
+    UnitSetup,
+    Bmod,
+    Pmod,
+    Pod,
+    Mod,
+    Padded(Padding),
+    #[strum(serialize = r"\smash")]
+    Smash,
+    #[strum(serialize = r"\rule")]
+    Rule,
+    Class(MathClass),
+    #[strum(serialize = r"\strut")]
+    Strut,
     Style(Style),
+    #[strum(serialize = r"\color")]
+    Color,
+    #[strum(serialize = r"\textcolor")]
+    TextColor,
+    #[strum(serialize = r"\colorbox")]
+    ColorBox,
+    #[strum(serialize = r"\fcolorbox")]
+    FColorBox,
+    #[strum(serialize = r"\definecolor")]
+    DefineColor,
+    #[strum(serialize = r"\href")]
+    Href,
+    /// `\htmlClass`, `\htmlId`, `\htmlStyle` or `\htmlData`.
+    Html(HtmlAttr),
+    #[strum(serialize = r"\url")]
+    Url,
+    #[strum(serialize = r"\boxed")]
+    Boxed,
+    #[strum(serialize = r"\fbox")]
+    FBox,
+    #[strum(serialize = r"\framebox")]
+    FrameBox,
+    #[strum(serialize = r"\bbox")]
+    BBox,
+    Enclose(Notation),
+    #[strum(serialize = r"\cancelto")]
+    CancelTo,
+    XArrow(Op),
+    SubStack,
+    #[strum(serialize = r"\hline")]
+    HorizontalRule(LineStyle),
+    #[strum(serialize = r"\cline")]
+    CLine,
+    #[strum(serialize = r"\tag")]
+    Tag,
+    #[strum(serialize = r"\notag")]
+    NoTag,
+    #[strum(serialize = r"\label")]
+    Label,
+    #[strum(serialize = r"\newcommand")]
+    NewCommand,
+    #[strum(serialize = r"\renewcommand")]
+    RenewCommand,
+    #[strum(serialize = r"\def")]
+    Def,
+    #[strum(serialize = "macro expansion error")]
+    MacroError,
     UnknownCommand(&'source str),
 }
 
@@ -0,0 +1,214 @@
+//! Raw character codes
+//!
+//! Characters can be given by their code point with `\unicode{x2A01}`, `\char"2A01`,
+//! `\symbol{"2A01}` or TeX's `^^^^2a01` notation. Since such a character can be anything,
+//! it is classified here with a condensed version of the Unicode math class table
+//! (`MathClass.txt`), so that operators and fences become `<mo>` and everything else `<mi>`.
+
+use crate::attribute::{ParenAttr, Stretchy};
+use crate::ops::Op;
+use crate::token::Token;
+
+/// The math class of a range of code points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    /// Binary operators, relations, punctuation and the like.
+    Operator,
+    /// Large operators like `⨁`.
+    Large,
+    /// Integrals, which are large operators with their own treatment of limits.
+    Integral,
+    /// Opening and closing brackets, which stretch by default.
+    Bracket,
+    /// Fences which open and close, like `‖`.
+    Fence,
+}
+
+/// Ranges of code points which are not ordinary, sorted and without overlaps.
+///
+/// Everything that is not listed, like letters, digits and most symbols, is ordinary.
+static CLASSES: &[(u32, u32, Class)] = &[
+    (0x0021, 0x0021, Class::Operator), // !
+    (0x0028, 0x0029, Class::Bracket),  // ( )
+    (0x002A, 0x002D, Class::Operator), // * + , -
+    (0x003A, 0x003E, Class::Operator), // : ; < = >
+    (0x005B, 0x005B, Class::Bracket),  // [
+    (0x005D, 0x005D, Class::Bracket),  // ]
+    (0x007B, 0x007B, Class::Bracket),  // {
+    (0x007C, 0x007C, Class::Fence),    // |
+    (0x007D, 0x007D, Class::Bracket),  // }
+    (0x00AC, 0x00AC, Class::Operator), // ¬
+    (0x00B1, 0x00B1, Class::Operator), // ±
+    (0x00B7, 0x00B7, Class::Operator), // ·
+    (0x00D7, 0x00D7, Class::Operator), // ×
+    (0x00F7, 0x00F7, Class::Operator), // ÷
+    (0x2016, 0x2016, Class::Fence),    // ‖
+    (0x2022, 0x2022, Class::Operator), // •
+    (0x2026, 0x2026, Class::Operator), // …
+    (0x2032, 0x2037, Class::Operator), // primes
+    (0x2044, 0x2044, Class::Operator), // ⁄
+    (0x2061, 0x2064, Class::Operator), // invisible operators
+    (0x2190, 0x21FF, Class::Operator), // arrows
+    (0x2200, 0x2201, Class::Operator), // ∀ ∁
+    (0x2203, 0x2204, Class::Operator), // ∃ ∄
+    (0x2208, 0x220D, Class::Operator), // ∈ … ∍
+    (0x220F, 0x2211, Class::Large),    // ∏ ∐ ∑
+    (0x2212, 0x221D, Class::Operator), // − … ∝
+    (0x2223, 0x222A, Class::Operator), // ∣ … ∪
+    (0x222B, 0x2233, Class::Integral), // ∫ … ∳
+    (0x2234, 0x22BF, Class::Operator), // ∴ … ⊿
+    (0x22C0, 0x22C3, Class::Large),    // ⋀ ⋁ ⋂ ⋃
+    (0x22C4, 0x22FF, Class::Operator), // ⋄ … ⋿
+    (0x2308, 0x230B, Class::Bracket),  // ⌈ ⌉ ⌊ ⌋
+    (0x2329, 0x232A, Class::Bracket),  // 〈 〉
+    (0x23B4, 0x23B5, Class::Operator), // ⎴ ⎵
+    (0x23DC, 0x23E1, Class::Operator), // ⏜ … ⏡
+    (0x2772, 0x2775, Class::Bracket),  // ❲ ❳ ❴ ❵
+    (0x27C0, 0x27C4, Class::Operator), // ⟀ … ⟄
+    (0x27C5, 0x27C6, Class::Bracket),  // ⟅ ⟆
+    (0x27C7, 0x27E5, Class::Operator), // ⟇ … ⟥
+    (0x27E6, 0x27EF, Class::Bracket),  // ⟦ … ⟯
+    (0x27F0, 0x297F, Class::Operator), // arrows
+    (0x2980, 0x2980, Class::Fence),    // ⦀
+    (0x2983, 0x2998, Class::Bracket),  // ⦃ … ⦘
+    (0x2999, 0x29D7, Class::Operator), // ⦙ … ⧗
+    (0x29D8, 0x29DB, Class::Bracket),  // ⧘ ⧙ ⧚ ⧛
+    (0x29DC, 0x29FB, Class::Operator), // ⧜ … ⧻
+    (0x29FC, 0x29FD, Class::Bracket),  // ⧼ ⧽
+    (0x29FE, 0x29FF, Class::Operator), // ⧾ ⧿
+    (0x2A00, 0x2A0A, Class::Large),    // ⨀ … ⨊
+    (0x2A0B, 0x2A1C, Class::Integral), // ⨋ … ⨜
+    (0x2A1D, 0x2AFB, Class::Operator), // ⨝ … ⫻
+    (0x2AFC, 0x2AFC, Class::Large),    // ⫼
+    (0x2AFD, 0x2AFE, Class::Operator), // ⫽ ⫾
+    (0x2AFF, 0x2AFF, Class::Large),    // ⫿
+    (0x2B00, 0x2BFF, Class::Operator), // arrows
+    (0x3008, 0x3011, Class::Bracket),  // 〈 … 】
+    (0x3014, 0x301B, Class::Bracket),  // 〔 … 〛
+    (0xFE68, 0xFE68, Class::Operator), // ﹨
+];
+
+/// Parse a character code like `x2A01`, `0x2A01`, `"2A01`, `'25001`, `` `A `` or `10753`.
+///
+/// Control characters are rejected, because they can't be shown anyway.
+pub(crate) fn parse_char_code(code: &str) -> Option<char> {
+    let code = code.trim();
+    let (digits, radix) = if let Some(hex) = code
+        .strip_prefix("0x")
+        .or_else(|| code.strip_prefix(['x', 'X', '"']))
+    {
+        (hex, 16)
+    } else if let Some(octal) = code.strip_prefix('\'') {
+        (octal, 8)
+    } else if let Some(quoted) = code.strip_prefix('`') {
+        // Like in TeX, a character may be escaped with a backslash.
+        let quoted = quoted.strip_prefix('\\').unwrap_or(quoted);
+        let mut chars = quoted.chars();
+        return chars
+            .next()
+            .filter(|c| chars.next().is_none() && !c.is_control());
+    } else {
+        (code, 10)
+    };
+    // `from_str_radix` would accept a sign.
+    if !digits.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let code = u32::from_str_radix(digits, radix).ok()?;
+    char::from_u32(code).filter(|c| !c.is_control())
+}
+
+/// Turn a character into the token that it would be if it had its own command.
+pub(crate) fn classify<'source>(c: char) -> Token<'source> {
+    // These need escaping or have their own tokens.
+    match c {
+        '<' => return Token::OpLessThan,
+        '>' => return Token::OpGreaterThan,
+        '&' => return Token::OpAmpersand,
+        _ => {}
+    }
+    let code = c as u32;
+    let class = CLASSES
+        .binary_search_by(|&(start, end, _)| {
+            if end < code {
+                std::cmp::Ordering::Less
+            } else if start > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|index| CLASSES[index].2);
+    let op = Op::from_char(c);
+    match class {
+        None => Token::Letter(c),
+        Some(Class::Operator) => Token::Operator(op),
+        Some(Class::Large) => Token::BigOp(op),
+        Some(Class::Integral) => Token::Integral(op),
+        Some(Class::Bracket) => Token::Paren(op, None, Stretchy::Always),
+        Some(Class::Fence) => Token::Paren(op, Some(ParenAttr::Ordinary), Stretchy::PrePostfix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_code_test() {
+        let problems = [
+            ("x2A01", Some('⨁')),
+            ("X2a01", Some('⨁')),
+            ("0x2A01", Some('⨁')),
+            ("\"2A01", Some('⨁')),
+            ("'25001", Some('⨁')),
+            ("10753", Some('⨁')),
+            (" x41 ", Some('A')),
+            ("`A", Some('A')),
+            ("`\\%", Some('%')),
+            ("`AB", None),
+            ("xD800", None),
+            ("x110000", None),
+            ("x0A", None),
+            ("+65", None),
+            ("x", None),
+            ("", None),
+        ];
+        for (input, expected) in problems {
+            assert_eq!(parse_char_code(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn table_is_sorted() {
+        for window in CLASSES.windows(2) {
+            let [(start, end, _), (next, _, _)] = window else {
+                unreachable!()
+            };
+            assert!(start <= end && end < next, "{start:X}..{end:X}");
+        }
+    }
+
+    #[test]
+    fn classify_test() {
+        assert_eq!(classify('a'), Token::Letter('a'));
+        assert_eq!(classify('∞'), Token::Letter('∞'));
+        assert_eq!(classify('≤'), Token::Operator(Op::from_char('≤')));
+        assert_eq!(classify('⨁'), Token::BigOp(Op::from_char('⨁')));
+        assert_eq!(classify('∯'), Token::Integral(Op::from_char('∯')));
+        assert_eq!(
+            classify('⟧'),
+            Token::Paren(Op::from_char('⟧'), None, Stretchy::Always)
+        );
+        assert_eq!(
+            classify('‖'),
+            Token::Paren(
+                Op::from_char('‖'),
+                Some(ParenAttr::Ordinary),
+                Stretchy::PrePostfix
+            )
+        );
+        assert_eq!(classify('<'), Token::OpLessThan);
+    }
+}
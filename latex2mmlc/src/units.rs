@@ -0,0 +1,713 @@
+//! Units and quantities
+//!
+//! The `\num`, `\unit`, `\qty` and `\ang` commands (and the older `\si` and `\SI`) follow
+//! the siunitx package. Numbers like `1.23(4)e-5` and units like `\metre\per\second` are
+//! parsed here, and the parser turns them into nodes according to the options which can
+//! be set with `\sisetup` or in an optional argument.
+
+use crate::{
+    arena::StringBuilder,
+    ops::{self, Op},
+};
+
+/// How units with a negative power are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PerMode {
+    /// `m s^{-1}`
+    Power,
+    /// `\frac{m}{s}`
+    Fraction,
+    /// `m/s`
+    Symbol,
+}
+
+/// Which parts of a number have their digits grouped in threes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GroupDigits {
+    None,
+    Integer,
+    Decimal,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct UnitOptions {
+    /// `output-decimal-marker`
+    pub(crate) decimal_marker: char,
+    /// `group-digits`
+    pub(crate) group_digits: GroupDigits,
+    /// `exponent-product`
+    pub(crate) exponent_product: Op,
+    /// `per-mode`
+    pub(crate) per_mode: PerMode,
+}
+
+impl Default for UnitOptions {
+    fn default() -> Self {
+        UnitOptions {
+            decimal_marker: '.',
+            group_digits: GroupDigits::All,
+            exponent_product: ops::MULTIPLICATION_SIGN,
+            per_mode: PerMode::Power,
+        }
+    }
+}
+
+impl UnitOptions {
+    /// Apply a list of options like `per-mode=fraction, group-digits=false`.
+    ///
+    /// On error, the option which was not understood is returned.
+    pub(crate) fn apply<'a>(&mut self, options: &'a str) -> Result<(), &'a str> {
+        // Commas in braces don't separate options, like in `output-decimal-marker={,}`.
+        let mut depth = 0usize;
+        let options = options.split(|c| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            c == ',' && depth == 0
+        });
+        for option in options {
+            if option.trim().is_empty() {
+                continue;
+            }
+            let (key, value) = option.split_once('=').ok_or(option)?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('{')
+                .and_then(|value| value.strip_suffix('}'))
+                .unwrap_or(value)
+                .trim();
+            match key.trim() {
+                "output-decimal-marker" => {
+                    let mut chars = value.chars();
+                    self.decimal_marker = match (chars.next(), chars.next()) {
+                        (Some(marker), None) => marker,
+                        _ => return Err(option),
+                    };
+                }
+                "group-digits" => {
+                    self.group_digits = match value {
+                        "true" | "all" => GroupDigits::All,
+                        "false" | "none" => GroupDigits::None,
+                        "integer" => GroupDigits::Integer,
+                        "decimal" => GroupDigits::Decimal,
+                        _ => return Err(option),
+                    };
+                }
+                "exponent-product" => {
+                    self.exponent_product = match value {
+                        r"\times" => ops::MULTIPLICATION_SIGN,
+                        r"\cdot" => ops::MIDDLE_DOT,
+                        r"\ast" => ops::ASTERISK_OPERATOR,
+                        _ => return Err(option),
+                    };
+                }
+                "per-mode" => {
+                    self.per_mode = match value {
+                        "power" => PerMode::Power,
+                        "fraction" => PerMode::Fraction,
+                        "symbol" => PerMode::Symbol,
+                        _ => return Err(option),
+                    };
+                }
+                _ => return Err(option),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The digits of a number, before and after the decimal marker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Digits<'a> {
+    pub(crate) integer: &'a str,
+    pub(crate) decimal: Option<&'a str>,
+}
+
+/// Numbers with fewer digits than this are not grouped, like in siunitx.
+const GROUP_MINIMUM_DIGITS: usize = 5;
+
+/// The separator between groups of digits, a thin space.
+const GROUP_SEPARATOR: char = '\u{2009}';
+
+impl Digits<'_> {
+    fn is_empty(&self) -> bool {
+        self.integer.is_empty() && self.decimal.is_none()
+    }
+
+    /// Write the digits with the decimal marker and digit grouping of the options.
+    pub(crate) fn write(&self, options: &UnitOptions, builder: &mut StringBuilder) {
+        let group_integer = matches!(
+            options.group_digits,
+            GroupDigits::Integer | GroupDigits::All
+        ) && self.integer.len() >= GROUP_MINIMUM_DIGITS;
+        // The integer part is grouped from the right.
+        for (i, digit) in self.integer.chars().enumerate() {
+            if group_integer && i > 0 && (self.integer.len() - i) % 3 == 0 {
+                builder.push_char(GROUP_SEPARATOR);
+            }
+            builder.push_char(digit);
+        }
+        if let Some(decimal) = self.decimal {
+            if self.integer.is_empty() {
+                builder.push_char('0');
+            }
+            builder.push_char(options.decimal_marker);
+            let group_decimal = matches!(
+                options.group_digits,
+                GroupDigits::Decimal | GroupDigits::All
+            ) && decimal.len() >= GROUP_MINIMUM_DIGITS;
+            // The decimal part is grouped from the left.
+            for (i, digit) in decimal.chars().enumerate() {
+                if group_decimal && i > 0 && i % 3 == 0 {
+                    builder.push_char(GROUP_SEPARATOR);
+                }
+                builder.push_char(digit);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Uncertainty<'a> {
+    /// An uncertainty in the last digits, like the `4` in `1.23(4)`.
+    Compact(Digits<'a>),
+    /// An uncertainty after `\pm` or `+-`.
+    Separate(Digits<'a>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Number<'a> {
+    /// A minus or plus sign, or a plus-minus sign.
+    pub(crate) sign: Option<Op>,
+    /// The mantissa, which may be empty if there is an exponent, like in `e5`.
+    pub(crate) mantissa: Option<Digits<'a>>,
+    pub(crate) uncertainty: Option<Uncertainty<'a>>,
+    /// The exponent of 10, with its sign.
+    pub(crate) exponent: Option<(Option<Op>, &'a str)>,
+}
+
+struct Scanner<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Scanner<'a> {
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn take(&mut self, len: usize) -> &'a str {
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let len = self.rest.find(|c| !f(c)).unwrap_or(self.rest.len());
+        self.take(len)
+    }
+
+    /// Consume `prefix` if the input starts with it.
+    fn eat(&mut self, prefix: &str) -> bool {
+        if let Some(rest) = self.rest.strip_prefix(prefix) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read_sign(&mut self) -> Option<Op> {
+        if self.eat("+-") || self.eat(r"\pm") {
+            Some(ops::PLUS_MINUS_SIGN)
+        } else if self.eat("-") {
+            Some(ops::MINUS_SIGN)
+        } else if self.eat("+") {
+            Some(ops::PLUS_SIGN)
+        } else {
+            None
+        }
+    }
+
+    /// Read digits with an optional decimal part; both `.` and `,` are decimal markers.
+    fn read_digits(&mut self) -> Digits<'a> {
+        let integer = self.take_while(|c| c.is_ascii_digit());
+        let decimal = if matches!(self.peek(), Some('.' | ',')) {
+            self.take(1);
+            Some(self.take_while(|c| c.is_ascii_digit()))
+        } else {
+            None
+        };
+        Digits { integer, decimal }
+    }
+
+    /// Read a braced argument, like the `{2}` of `\tothe{2}`.
+    fn read_argument(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest.strip_prefix('{')?;
+        let end = rest.find('}')?;
+        self.rest = &rest[end + 1..];
+        Some(rest[..end].trim())
+    }
+}
+
+/// Parse the argument of `\num`, like `-1.23(4)e-5`.
+///
+/// Returns `None` if the number is not valid.
+pub(crate) fn parse_number(input: &str) -> Option<Number<'_>> {
+    let mut s = Scanner { rest: input.trim() };
+    let sign = s.read_sign();
+    s.skip_whitespace();
+    let mantissa = Some(s.read_digits()).filter(|digits| !digits.is_empty());
+    s.skip_whitespace();
+    let uncertainty = if s.eat("(") {
+        let digits = s.read_digits();
+        if digits.is_empty() || !s.eat(")") {
+            return None;
+        }
+        Some(Uncertainty::Compact(digits))
+    } else if s.eat("+-") || s.eat(r"\pm") {
+        s.skip_whitespace();
+        let digits = s.read_digits();
+        if digits.is_empty() {
+            return None;
+        }
+        Some(Uncertainty::Separate(digits))
+    } else {
+        None
+    };
+    s.skip_whitespace();
+    let exponent = if matches!(s.peek(), Some('e' | 'E' | 'd' | 'D')) {
+        s.take(1);
+        let sign = s.read_sign();
+        let digits = s.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return None;
+        }
+        Some((sign, digits))
+    } else {
+        None
+    };
+    s.skip_whitespace();
+    if !s.rest.is_empty() || (mantissa.is_none() && exponent.is_none()) {
+        return None;
+    }
+    // An uncertainty needs something to be uncertain about.
+    if mantissa.is_none() && uncertainty.is_some() {
+        return None;
+    }
+    Some(Number {
+        sign,
+        mantissa,
+        uncertainty,
+        exponent,
+    })
+}
+
+/// A unit with its prefix and power, like the `\kilo\metre\squared` in `\unit{\kilo\metre\squared}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Unit<'a> {
+    pub(crate) prefix: &'a str,
+    pub(crate) symbol: &'a str,
+    pub(crate) power: Option<&'a str>,
+    /// Whether the unit was preceded by `\per`, so that its power is negative.
+    pub(crate) per: bool,
+}
+
+impl Unit<'_> {
+    /// Whether the unit follows a number without any space, like `\degree`.
+    pub(crate) fn is_unspaced(&self) -> bool {
+        self.prefix.is_empty() && matches!(self.symbol, "°" | "′" | "″")
+    }
+}
+
+static PREFIXES: phf::Map<&'static str, &'static str> = phf::phf_map! {
+    "quecto" => "q",
+    "ronto" => "r",
+    "yocto" => "y",
+    "zepto" => "z",
+    "atto" => "a",
+    "femto" => "f",
+    "pico" => "p",
+    "nano" => "n",
+    "micro" => "µ",
+    "milli" => "m",
+    "centi" => "c",
+    "deci" => "d",
+    "deca" => "da",
+    "deka" => "da",
+    "hecto" => "h",
+    "kilo" => "k",
+    "mega" => "M",
+    "giga" => "G",
+    "tera" => "T",
+    "peta" => "P",
+    "exa" => "E",
+    "zetta" => "Z",
+    "yotta" => "Y",
+    "ronna" => "R",
+    "quetta" => "Q",
+};
+
+static UNITS: phf::Map<&'static str, &'static str> = phf::phf_map! {
+    // SI base units
+    "ampere" => "A",
+    "candela" => "cd",
+    "gram" => "g",
+    "kelvin" => "K",
+    "kilogram" => "kg",
+    "meter" => "m",
+    "metre" => "m",
+    "mole" => "mol",
+    "second" => "s",
+    // Derived units
+    "becquerel" => "Bq",
+    "coulomb" => "C",
+    "degreeCelsius" => "°C",
+    "farad" => "F",
+    "gray" => "Gy",
+    "henry" => "H",
+    "hertz" => "Hz",
+    "joule" => "J",
+    "katal" => "kat",
+    "lumen" => "lm",
+    "lux" => "lx",
+    "newton" => "N",
+    "ohm" => "Ω",
+    "pascal" => "Pa",
+    "radian" => "rad",
+    "siemens" => "S",
+    "sievert" => "Sv",
+    "steradian" => "sr",
+    "tesla" => "T",
+    "volt" => "V",
+    "watt" => "W",
+    "weber" => "Wb",
+    // Non-SI units
+    "angstrom" => "Å",
+    "arcminute" => "′",
+    "arcsecond" => "″",
+    "astronomicalunit" => "au",
+    "atomicmassunit" => "u",
+    "bar" => "bar",
+    "barn" => "b",
+    "bel" => "B",
+    "dalton" => "Da",
+    "day" => "d",
+    "decibel" => "dB",
+    "degree" => "°",
+    "electronvolt" => "eV",
+    "hectare" => "ha",
+    "hour" => "h",
+    "knot" => "kn",
+    "liter" => "L",
+    "litre" => "L",
+    "minute" => "min",
+    "mmHg" => "mmHg",
+    "nauticalmile" => "M",
+    "neper" => "Np",
+    "percent" => "%",
+    "tonne" => "t",
+    // Abbreviations
+    "A" => "A",
+    "Bq" => "Bq",
+    "C" => "C",
+    "F" => "F",
+    "GHz" => "GHz",
+    "GeV" => "GeV",
+    "Hz" => "Hz",
+    "J" => "J",
+    "K" => "K",
+    "L" => "L",
+    "MHz" => "MHz",
+    "MeV" => "MeV",
+    "N" => "N",
+    "Pa" => "Pa",
+    "V" => "V",
+    "W" => "W",
+    "cm" => "cm",
+    "dB" => "dB",
+    "eV" => "eV",
+    "g" => "g",
+    "h" => "h",
+    "kHz" => "kHz",
+    "kJ" => "kJ",
+    "kN" => "kN",
+    "kPa" => "kPa",
+    "kV" => "kV",
+    "kW" => "kW",
+    "keV" => "keV",
+    "kg" => "kg",
+    "km" => "km",
+    "m" => "m",
+    "mA" => "mA",
+    "mL" => "mL",
+    "mV" => "mV",
+    "mg" => "mg",
+    "mm" => "mm",
+    "mol" => "mol",
+    "ms" => "ms",
+    "nm" => "nm",
+    "ns" => "ns",
+    "s" => "s",
+    "uA" => "µA",
+    "ug" => "µg",
+    "um" => "µm",
+    "us" => "µs",
+};
+
+/// Parse the argument of `\unit`, which may use the unit macros like
+/// `\kilo\metre\per\second\squared`, or literal units like `kg.m/s^2`.
+///
+/// On error, the part which was not understood is returned.
+pub(crate) fn parse_units(input: &str) -> Result<Vec<Unit<'_>>, &str> {
+    let mut s = Scanner { rest: input };
+    let mut units = Vec::new();
+    // What applies to the next unit.
+    let mut prefix = "";
+    let mut power = None;
+    let mut per = false;
+    loop {
+        s.skip_whitespace();
+        let Some(c) = s.peek() else {
+            break;
+        };
+        let start = s.rest;
+        match c {
+            '\\' => {
+                s.take(1);
+                let name = s.take_while(|c| c.is_ascii_alphabetic());
+                let command = &start[..name.len() + 1];
+                match name {
+                    "per" => per = true,
+                    "square" => power = Some("2"),
+                    "cubic" => power = Some("3"),
+                    "raiseto" => power = Some(s.read_argument().ok_or(command)?),
+                    "squared" | "cubed" | "tothe" => {
+                        let value = match name {
+                            "squared" => "2",
+                            "cubed" => "3",
+                            _ => s.read_argument().ok_or(command)?,
+                        };
+                        let unit: &mut Unit = units.last_mut().ok_or(command)?;
+                        unit.power = Some(value);
+                    }
+                    _ => {
+                        if let Some(p) = PREFIXES.get(name) {
+                            prefix = p;
+                            continue;
+                        }
+                        let symbol = UNITS.get(name).ok_or(command)?;
+                        units.push(Unit {
+                            prefix: std::mem::take(&mut prefix),
+                            symbol,
+                            power: power.take(),
+                            per: std::mem::take(&mut per),
+                        });
+                    }
+                }
+            }
+            // Products of literal units.
+            '.' | '~' => {
+                s.take(1);
+            }
+            '/' => {
+                s.take(1);
+                per = true;
+            }
+            '^' => {
+                s.take(1);
+                s.skip_whitespace();
+                let value = match s.peek() {
+                    Some('{') => s.read_argument().ok_or(start)?,
+                    _ => s.take_while(|c| c.is_ascii_digit() || c == '-'),
+                };
+                let unit = units.last_mut().ok_or(start)?;
+                if value.is_empty() {
+                    return Err(start);
+                }
+                unit.power = Some(value);
+            }
+            _ => {
+                let symbol = s.take_while(|c| {
+                    !c.is_whitespace() && !c.is_ascii_digit() && !"\\.~/^{}".contains(c)
+                });
+                if symbol.is_empty() {
+                    return Err(start);
+                }
+                units.push(Unit {
+                    prefix: std::mem::take(&mut prefix),
+                    symbol,
+                    power: power.take(),
+                    per: std::mem::take(&mut per),
+                });
+            }
+        }
+    }
+    // A prefix or power without a unit is not allowed.
+    if units.is_empty() || !prefix.is_empty() || power.is_some() || per {
+        return Err(input);
+    }
+    Ok(units)
+}
+
+/// Parse the argument of `\ang`, which is a decimal angle or degrees, minutes and seconds
+/// separated by `;`, like `12;34;56`.
+///
+/// Returns the numbers together with the symbols which follow them.
+pub(crate) fn parse_angle(input: &str) -> Option<Vec<(Number<'_>, &'static str)>> {
+    const SYMBOLS: [&str; 3] = ["°", "′", "″"];
+    let parts = input.split(';');
+    if parts.clone().count() > SYMBOLS.len() {
+        return None;
+    }
+    let mut angle = Vec::new();
+    for (part, symbol) in parts.zip(SYMBOLS) {
+        if part.trim().is_empty() {
+            continue;
+        }
+        angle.push((parse_number(part)?, symbol));
+    }
+    if angle.is_empty() {
+        return None;
+    }
+    Some(angle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::{Arena, Buffer};
+
+    #[test]
+    fn number_test() {
+        let problems = [
+            ("1.23(4)e-5", true),
+            ("-0,5", true),
+            (r"3 \pm 0.2", true),
+            ("+-1", true),
+            ("e5", true),
+            (".5", true),
+            ("1.2.3", false),
+            ("1e", false),
+            ("(4)", false),
+            ("1x", false),
+            ("", false),
+        ];
+        for (input, valid) in problems {
+            assert_eq!(parse_number(input).is_some(), valid, "{input}");
+        }
+        let number = parse_number("-1.23(4)e-5").unwrap();
+        assert_eq!(number.sign, Some(ops::MINUS_SIGN));
+        assert_eq!(
+            number.mantissa,
+            Some(Digits {
+                integer: "1",
+                decimal: Some("23")
+            })
+        );
+        assert_eq!(
+            number.uncertainty,
+            Some(Uncertainty::Compact(Digits {
+                integer: "4",
+                decimal: None
+            }))
+        );
+        assert_eq!(number.exponent, Some((Some(ops::MINUS_SIGN), "5")));
+    }
+
+    #[test]
+    fn digit_grouping_test() {
+        let arena = Arena::new();
+        let mut buffer = Buffer::new(0);
+        let mut format = |input: &str, options: &UnitOptions| -> String {
+            let mut builder = buffer.get_builder();
+            parse_number(input)
+                .unwrap()
+                .mantissa
+                .unwrap()
+                .write(options, &mut builder);
+            builder.finish(&arena).to_string()
+        };
+        let default = UnitOptions::default();
+        assert_eq!(format("1234", &default), "1234");
+        assert_eq!(format("12345", &default), "12\u{2009}345");
+        assert_eq!(
+            format("1234567.1234", &default),
+            "1\u{2009}234\u{2009}567.1234"
+        );
+        assert_eq!(format("0.123456", &default), "0.123\u{2009}456");
+        assert_eq!(format(".5", &default), "0.5");
+        let mut options = UnitOptions::default();
+        options
+            .apply("group-digits=integer, output-decimal-marker={,}")
+            .unwrap();
+        assert_eq!(format("12345.123456", &options), "12\u{2009}345,123456");
+    }
+
+    #[test]
+    fn options_test() {
+        let mut options = UnitOptions::default();
+        options
+            .apply(r"per-mode = fraction, exponent-product=\cdot,")
+            .unwrap();
+        assert_eq!(options.per_mode, PerMode::Fraction);
+        assert_eq!(options.exponent_product, ops::MIDDLE_DOT);
+        assert_eq!(
+            options.apply("per-mode=reciprocal"),
+            Err("per-mode=reciprocal")
+        );
+        assert_eq!(options.apply("color=red"), Err("color=red"));
+        assert_eq!(options.apply("group-digits"), Err("group-digits"));
+    }
+
+    #[test]
+    fn units_test() {
+        let units = parse_units(r"\kilo\metre\per\square\second").unwrap();
+        assert_eq!(
+            units,
+            [
+                Unit {
+                    prefix: "k",
+                    symbol: "m",
+                    power: None,
+                    per: false
+                },
+                Unit {
+                    prefix: "",
+                    symbol: "s",
+                    power: Some("2"),
+                    per: true
+                },
+            ]
+        );
+        let units = parse_units("kg.m/s^2").unwrap();
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[2].symbol, "s");
+        assert_eq!(units[2].power, Some("2"));
+        assert!(units[2].per);
+        assert_eq!(parse_units(r"\metre\tothe{4}").unwrap()[0].power, Some("4"));
+        assert_eq!(parse_units(r"\metre\furlong"), Err(r"\furlong"));
+        assert_eq!(parse_units(r"\squared"), Err(r"\squared"));
+        assert_eq!(parse_units(r"\metre\per"), Err(r"\metre\per"));
+        assert_eq!(parse_units(""), Err(""));
+    }
+
+    #[test]
+    fn angle_test() {
+        assert_eq!(parse_angle("12;34;56").unwrap().len(), 3);
+        let angle = parse_angle(";;1.5").unwrap();
+        assert_eq!(angle.len(), 1);
+        assert_eq!(angle[0].1, "″");
+        assert!(parse_angle("1;2;3;4").is_none());
+        assert!(parse_angle(";").is_none());
+        assert!(parse_angle("x").is_none());
+    }
+}
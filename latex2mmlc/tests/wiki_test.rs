@@ -262,8 +262,8 @@ fn wiki_test() {
         }
     }
     assert_eq!(n_match, 10);
-    assert_eq!(n_diff, 165);
-    assert_eq!(n_fail, 43);
+    assert_eq!(n_diff, 180);
+    assert_eq!(n_fail, 28);
 }
 
 /// Prettify HTML input
@@ -345,8 +345,8 @@ fn test_nonfailing_wiki_tests() {
             25,
             r"\Im, \imath, \jmath, \Bbbk, \ell, \mho, \wp, \Re, \circledS, \S, \P, \AA",
         ),
-        // (26, r"s_k \equiv 0 \pmod{m}"),
-        // (27, r"a \bmod b"),
+        (26, r"s_k \equiv 0 \pmod{m}"),
+        (27, r"a \bmod b"),
         (28, r"\gcd(m, n), \operatorname{lcm}(m, n)"),
         // (29, r"\mid, \nmid, \shortmid, \nshortmid"),
         // (30, r"\surd, \sqrt{2}, \sqrt[n]{2}, \sqrt[3]{\frac{x^3+y^3}{2}}"),
@@ -461,10 +461,10 @@ fn test_nonfailing_wiki_tests() {
         (106, r"10^{30} a^{2+2} \\ a_{i,j} b_{f'}"),
         (107, r"x_2^3 \\ {x_2}^3"),
         (108, r"10^{10^{8}}"),
-        // (
-        //     109,
-        //     r"\overset{\alpha}{\omega} \\ \underset{\alpha}{\omega} \\ \overset{\alpha}{\underset{\gamma}{\omega}}\\ \stackrel{\alpha}{\omega}",
-        // ),
+        (
+            109,
+            r"\overset{\alpha}{\omega} \\ \underset{\alpha}{\omega} \\ \overset{\alpha}{\underset{\gamma}{\omega}}\\ \stackrel{\alpha}{\omega}",
+        ),
         (110, r"x', y'', f', f'' \\ x^\prime, y^{\prime\prime}"),
         (111, r"\dot{x}, \ddot{x}"),
         (
@@ -472,7 +472,7 @@ fn test_nonfailing_wiki_tests() {
             r"\hat a \ \bar b \ \vec c \\ \overrightarrow{a b} \ \overleftarrow{c d}\\ \widehat{d e f} \\ \overline{g h i} \ \underline{j k l}",
         ),
         (113, r"\overset{\frown} {AB}"),
-        // (114, r"A \xleftarrow{n+\mu-1} B \xrightarrow[T]{n\pm i-1} C"),
+        (114, r"A \xleftarrow{n+\mu-1} B \xrightarrow[T]{n\pm i-1} C"),
         (115, r"\overbrace{ 1+2+\cdots+100 }^{5050}"),
         (116, r"\underbrace{ a+b+\cdots+z }_{26}"),
         (117, r"\frac{2}{4}=0.5"),
@@ -480,7 +480,10 @@ fn test_nonfailing_wiki_tests() {
             118,
             r"\dfrac{2}{4} = 0.5 \qquad \dfrac{2}{c + \dfrac{2}{d + \dfrac{2}{4}}} = a",
         ),
-        // (119, r"\cfrac{x}{1 + \cfrac{\cancel{y}} {\cancel{y}}} = \cfrac{x}{2}"),
+        (
+            119,
+            r"\cfrac{x}{1 + \cfrac{\cancel{y}} {\cancel{y}}} = \cfrac{x}{2}",
+        ),
         (120, r"\binom{n}{k}"),
         (121, r"\dbinom{n}{k}"),
         (122, r"\begin{matrix} x & y \\ z & v \end{matrix}"),
@@ -502,7 +505,10 @@ fn test_nonfailing_wiki_tests() {
             r"\begin{cases} 3x + 5y + z \\ 7x - 2y + 4z \\ -6x + 3y + 2z \end{cases}",
         ),
         (131, r"f(x) \,\!"),
-        // (132, r"\begin{array}{|c|c|c|} a & b & S \\ \hline 0 & 0 & 1 \\ 0 & 1 & 1 \\ 1 & 0 & 1 \\ 1 & 1 & 0 \\ \end{array}"),
+        (
+            132,
+            r"\begin{array}{|c|c|c|} a & b & S \\ \hline 0 & 0 & 1 \\ 0 & 1 & 1 \\ 1 & 0 & 1 \\ 1 & 1 & 0 \\ \end{array}",
+        ),
         (133, r"( \frac{1}{2} )^n"),
         (134, r"\left ( \frac{1}{2} \right )^n"),
         (135, r"\left ( \frac{a}{b} \right )"),
@@ -668,12 +674,30 @@ fn test_nonfailing_wiki_tests() {
         (187, r"\text{if} n \text{is even}"),
         (188, r"\text{if }n\text{ is even}"),
         (189, r"\text{if}~n\ \text{is even}"),
-        // (190, r"{\color{Blue}x^2}+{\color{Orange}2x}- {\color{LimeGreen}1}"),
-        // (191, r"x_{1,2}=\frac{{\color{Blue}-b}\pm \sqrt{\color{Red}b^2-4ac}}{\color{Green}2a }"),
-        // (192, r"{\color{Blue}x^2}+{\color{Orange}2x}- {\color{LimeGreen}1}"),
-        // (193, r"\color{Blue}x^2\color{Black}+\color{Orange} 2x\color{Black}-\color{LimeGreen}1"),
-        // (194, r"\color{Blue}{x^2}+\color{Orange}{2x}- \color{LimeGreen}{1}"),
-        // (195, r"\definecolor{myorange}{rgb}{1,0.65,0.4} \color{myorange}e^{i \pi}\color{Black} + 1= 0"),
+        (
+            190,
+            r"{\color{Blue}x^2}+{\color{Orange}2x}- {\color{LimeGreen}1}",
+        ),
+        (
+            191,
+            r"x_{1,2}=\frac{{\color{Blue}-b}\pm \sqrt{\color{Red}b^2-4ac}}{\color{Green}2a }",
+        ),
+        (
+            192,
+            r"{\color{Blue}x^2}+{\color{Orange}2x}- {\color{LimeGreen}1}",
+        ),
+        (
+            193,
+            r"\color{Blue}x^2\color{Black}+\color{Orange} 2x\color{Black}-\color{LimeGreen}1",
+        ),
+        (
+            194,
+            r"\color{Blue}{x^2}+\color{Orange}{2x}- \color{LimeGreen}{1}",
+        ),
+        (
+            195,
+            r"\definecolor{myorange}{rgb}{1,0.65,0.4} \color{myorange}e^{i \pi}\color{Black} + 1= 0",
+        ),
         (
             196,
             r"a \qquad b \\ a \quad b \\ a\ b \\ a \text{ } b \\ a\;b \\ a\,b \\ ab \\ a b \\ \mathit{ab} \\ a\!b",
@@ -684,7 +708,7 @@ fn test_nonfailing_wiki_tests() {
         // (200, r"| \mathord\uparrow \rangle"),
         // (201, r"\wideparen{AB}"),
         // (202, r"\dddot{x}"),
-        // (203, r"\sout{q}"),
+        (203, r"\sout{q}"),
         // (204, r"\mathrlap{\,/}{=}"),
         // (205, r"\text{\textsf{textual description}}"),
         (206, r"α π"),
@@ -718,7 +742,7 @@ fn test_nonfailing_wiki_tests() {
 
     for (num, problem) in problems.into_iter() {
         let mathml = latex_to_mathml(problem, crate::Display::Inline, true)
-            .expect(format!("failed to convert `{}`", problem).as_str());
+            .unwrap_or_else(|_| panic!("failed to convert `{}`", problem));
         let name = format!("wiki{:03}", num);
         assert_snapshot!(name.as_str(), &mathml, problem);
     }
@@ -204,7 +204,7 @@ mod tests {
         block_delim: (&str, &str),
     ) -> Result<String, ConversionError<'static>> {
         let mut replacer = Replacer::new(inline_delim, block_delim);
-        match replacer.replace(input, |buf, content, typ| mock_convert(buf, content, typ)) {
+        match replacer.replace(input, mock_convert) {
             Ok(s) => Ok(s),
             Err(e) => match &e.1 {
                 // The following is needed to do a kind of "lifetime laundering".
@@ -1,3 +1,5 @@
+#![cfg(target_arch = "wasm32")]
+
 use wasm_bindgen_test::*;
 
 use latex2mmlc::arena::NodeList;